@@ -0,0 +1,45 @@
+// With the header feature, generates npuzzle.h in OUT_DIR from the extern
+// functions and constants of src/ffi.rs. The committed include/npuzzle.h is
+// checked against it by the tests of ffi.rs, without the feature nothing is
+// generated and cbindgen is not built.
+#[cfg(feature = "header")]
+extern crate cbindgen;
+
+#[cfg(feature = "header")]
+fn header() -> Result<(), String> {
+    use std::env;
+    use std::fs::File;
+    use std::path::Path;
+
+    let dir = env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?;
+    let out = env::var("OUT_DIR").map_err(|e| e.to_string())?;
+    let config = cbindgen::Config::from_file(Path::new(&dir).join("cbindgen.toml"))?;
+
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src");
+
+    let bindings = cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .map_err(|e| format!("could not generate npuzzle.h: {}", e))?;
+    let file = File::create(Path::new(&out).join("npuzzle.h")).map_err(|e| format!("could not write npuzzle.h: {}", e))?;
+
+    bindings.write(file);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "header"))]
+fn header() -> Result<(), String> {
+    Ok(())
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if let Err(e) = header() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,48 @@
+// Whatever a file holds, reading it gives a puzzle or an error, never a panic.
+// Puzzles given back are valid and read back the same once written out.
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate npuzzle_lib;
+
+use npuzzle_lib::diagnostics;
+use npuzzle_lib::input_parser;
+use npuzzle_lib::types::ParsedPuzzle;
+use npuzzle_lib::writer;
+
+fn check_valid(puzzle: &ParsedPuzzle) {
+    assert_eq!(input_parser::puzzle_error(puzzle), None);
+
+    let written = writer::to_string(puzzle, &writer::Header::default());
+    let read = input_parser::parse_str(&written).unwrap();
+    assert_eq!(read.size, puzzle.size);
+    assert_eq!(read.container, puzzle.container);
+}
+
+fuzz_target!(|data: &[u8]| {
+    // files that are not UTF-8 are refused before being parsed
+    let content = match std::str::from_utf8(data) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+
+    for d in diagnostics::check(content) {
+        diagnostics::render(&d, content, "fuzz");
+    }
+    if let Ok(puzzle) = input_parser::parse_any(content) {
+        check_valid(&puzzle);
+    }
+    for &(_, parser) in input_parser::FORMATS.iter() {
+        if let Ok(puzzle) = parser(content) {
+            check_valid(&puzzle);
+        }
+    }
+    if let Ok(labeled) = input_parser::parse_labeled(content) {
+        check_valid(&labeled.start);
+        if let Some(ref goal) = labeled.goal {
+            check_valid(goal);
+            assert_eq!(goal.size, labeled.start.size);
+        }
+    }
+});
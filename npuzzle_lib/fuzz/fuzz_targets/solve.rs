@@ -0,0 +1,97 @@
+// Solves small boards under a budget. The first byte picks the size, 3 or 4,
+// and the goal, snail or classic. The next ones are ranked to give the tiles,
+// so that any input is a board and the tiles of a board give itself back.
+#![no_main]
+
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate npuzzle_lib;
+
+use npuzzle_lib::checker;
+use npuzzle_lib::generator;
+use npuzzle_lib::heuristics;
+use npuzzle_lib::solver;
+use npuzzle_lib::solver::Budget;
+use npuzzle_lib::solver::Search;
+use npuzzle_lib::types::Atom;
+use npuzzle_lib::types::Heuristic;
+use npuzzle_lib::types::Problem;
+use npuzzle_lib::types::Puzzle;
+use npuzzle_lib::types::SearchOptions;
+use npuzzle_lib::types::Status;
+
+const EXPANDED: usize = 1_000;
+
+// Tile i is the rank of byte i among the first len bytes, ties broken by
+// position. Missing bytes rank after all the others.
+fn board(bytes: &[u8], len: usize) -> Puzzle {
+    let key = |i: usize| (bytes.get(i).map_or(256, |&b| b as u16), i);
+    let mut order: Vec<usize> = (0..len).collect();
+    let mut tiles = vec![0; len];
+
+    order.sort_by_key(|&i| key(i));
+    for (rank, &i) in order.iter().enumerate() {
+        tiles[i] = rank as Atom;
+    }
+
+    tiles
+}
+
+fuzz_target!(|data: &[u8]| {
+    let flags = match data.first() {
+        Some(&flags) => flags,
+        None => return,
+    };
+    let size: Atom = 3 + (flags & 1) as Atom;
+    let goal = if flags & 2 == 0 {
+        generator::snail(size)
+    } else {
+        generator::classic(size)
+    };
+    let start = board(&data[1..], size as usize * size as usize);
+
+    let explanation = checker::explain(&start, &goal, size);
+    checker::format_explanation(&explanation);
+    assert_eq!(explanation.is_solvable(), checker::is_solvable(&start, &goal, size));
+    if let Some((a, b)) = explanation.swap {
+        let mut fixed = start.clone();
+        let i = fixed.iter().position(|&t| t == a).unwrap();
+        let j = fixed.iter().position(|&t| t == b).unwrap();
+
+        fixed.swap(i, j);
+        assert!(checker::is_solvable(&fixed, &goal, size));
+    }
+
+    let problem = Problem {
+        start: start.clone(),
+        end: goal.clone(),
+        size,
+        heuristic: Heuristic::Conflicts,
+        g_weight: 1,
+        h_weight: 1,
+    };
+    let budget = Budget {
+        expanded: Some(EXPANDED),
+        time: None,
+    };
+    let mut search = Search::new(&problem);
+    search.run(&mut SearchOptions::default(), &budget);
+    let solution = search.solution();
+
+    match solution.status {
+        Status::Solved => {
+            assert!(explanation.is_solvable());
+            assert_eq!(solution.path.first(), Some(&start));
+            assert_eq!(solution.path.last(), Some(&goal));
+            // the heuristic never overestimates
+            assert!(heuristics::linear_conflicts(&start, &goal, size) as usize <= solution.path.len() - 1);
+            for w in solution.path.windows(2) {
+                let pos = w[0].iter().position(|&t| t == 0).unwrap() as Atom;
+
+                assert!(solver::neighbors(&w[0], pos, size).iter().any(|n| n.0 == w[1]));
+            }
+        }
+        Status::Unsolvable => assert!(!explanation.is_solvable()),
+        _ => {}
+    }
+});
@@ -0,0 +1,152 @@
+/*
+ * C interface of npuzzle_lib, built as a cdylib by `cargo build --release`
+ * in npuzzle_lib.
+ *
+ *     uint16_t tiles[9] = {1, 2, 3, 8, 0, 4, 7, 6, 5};
+ *     npuzzle_problem *p = npuzzle_problem_new(tiles, 3, NPUZZLE_GOAL_SNAIL);
+ *     npuzzle_solution *s = npuzzle_solve(p, 1000000, 500);
+ *
+ *     if (s && s->status == NPUZZLE_SOLVED)
+ *         printf("%.*s\n", (int)s->moves_len, (const char *)s->moves);
+ *     npuzzle_solution_free(s);
+ *     npuzzle_problem_free(p);
+ *
+ * No function unwinds into the caller. Invalid arguments and internal
+ * failures are reported by a NULL pointer or a negative return value, as
+ * long as the library is built with panic = "unwind" like its release
+ * profile. Built with panic = "abort", an internal failure aborts instead.
+ */
+
+#ifndef NPUZZLE_H
+#define NPUZZLE_H
+
+/* Generated from src/ffi.rs by build.rs with the header feature, do not edit */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Goal types for npuzzle_problem_new
+ */
+#define NPUZZLE_GOAL_SNAIL 0
+
+#define NPUZZLE_GOAL_CLASSIC 1
+
+/**
+ * Heuristics for npuzzle_problem_set_heuristic, linear conflicts by default
+ */
+#define NPUZZLE_HEURISTIC_CONFLICTS 0
+
+#define NPUZZLE_HEURISTIC_HAMMING 1
+
+#define NPUZZLE_HEURISTIC_MANHATTAN 2
+
+/**
+ * Values of npuzzle_solution.status
+ */
+#define NPUZZLE_SOLVED 0
+
+#define NPUZZLE_UNSOLVABLE 1
+
+#define NPUZZLE_OUT_OF_BUDGET 2
+
+typedef struct npuzzle_problem npuzzle_problem;
+
+typedef struct {
+  int status;
+  /**
+   * 'U', 'D', 'L' or 'R' for the direction each tile slides into the
+   * blank, the blank itself moving the opposite way. Not NUL terminated.
+   */
+  uint8_t *moves;
+  size_t moves_len;
+  uint64_t expanded;
+  uint64_t closed_states;
+  uint64_t max_states;
+} npuzzle_solution;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * `tiles` holds size * size values, 0 being the blank. NULL if invalid.
+ *
+ * # Safety
+ *
+ * `tiles` is NULL or points to size * size readable values.
+ */
+npuzzle_problem *npuzzle_problem_new(const uint16_t *tiles, size_t size, int goal);
+
+/**
+ * Same as npuzzle_problem_new, with the goal given tile by tile.
+ *
+ * # Safety
+ *
+ * `tiles` and `goal_tiles` are each NULL or point to size * size readable
+ * values.
+ */
+npuzzle_problem *npuzzle_problem_new_with_goal(const uint16_t *tiles,
+                                               const uint16_t *goal_tiles,
+                                               size_t size);
+
+/**
+ * # Safety
+ *
+ * `problem` is NULL or was returned by npuzzle_problem_new or
+ * npuzzle_problem_new_with_goal, and is not used afterwards.
+ */
+void npuzzle_problem_free(npuzzle_problem *problem);
+
+/**
+ * 0 on success, -1 on invalid arguments
+ *
+ * # Safety
+ *
+ * `problem` is NULL or a live problem not used by another thread meanwhile.
+ */
+int npuzzle_problem_set_heuristic(npuzzle_problem *problem, int heuristic);
+
+/**
+ * 0 on success, -1 on invalid arguments
+ *
+ * # Safety
+ *
+ * `problem` is NULL or a live problem not used by another thread meanwhile.
+ */
+int npuzzle_problem_set_weights(npuzzle_problem *problem, uint64_t g_weight, uint64_t h_weight);
+
+/**
+ * 1 if solvable, 0 if not, -1 on invalid arguments
+ *
+ * # Safety
+ *
+ * `problem` is NULL or a live problem.
+ */
+int npuzzle_is_solvable(const npuzzle_problem *problem);
+
+/**
+ * Zero for either budget means no limit. Free the result with
+ * npuzzle_solution_free.
+ *
+ * # Safety
+ *
+ * `problem` is NULL or a live problem.
+ */
+npuzzle_solution *npuzzle_solve(const npuzzle_problem *problem,
+                                uint64_t max_expanded,
+                                uint64_t timeout_ms);
+
+/**
+ * # Safety
+ *
+ * `solution` is NULL or was returned by npuzzle_solve, and is not used
+ * afterwards.
+ */
+void npuzzle_solution_free(npuzzle_solution *solution);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* NPUZZLE_H */
@@ -0,0 +1,193 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use generator;
+use heuristics::HEURISTICS;
+use solver::SOLVERS;
+use types::Atom;
+use types::ParsedPuzzle;
+use types::Problem;
+use types::Puzzle;
+use types::SearchOptions;
+use types::Statistic;
+
+// Bump whenever SUITE changes, so that numbers are only compared within a
+// version
+pub static SUITE_VERSION: u32 = 1;
+
+// (size, instances, random moves from the goal, seed of the first instance)
+static SUITE: [(Atom, u64, u64, u64); 2] = [(3, 8, 200, 3000), (4, 4, 40, 4000)];
+
+pub struct Instance {
+    pub name: String,
+    pub start: Puzzle,
+    pub end: Puzzle,
+    pub size: Atom,
+}
+
+pub struct Record {
+    pub instance: String,
+    pub size: Atom,
+    pub solver: &'static str,
+    pub heuristic: &'static str,
+    pub expanded: Statistic,
+    pub max_states: Statistic,
+    pub length: Option<usize>,
+    pub time: Duration,
+}
+
+pub fn suite() -> Vec<Instance> {
+    let mut instances = Vec::new();
+
+    for &(size, count, iterations, seed) in SUITE.iter() {
+        let goal = ParsedPuzzle {
+            container: generator::snail(size),
+            size,
+        };
+
+        for i in 0..count {
+            let start = generator::generate_seeded_puzzle(&goal, iterations, seed + i);
+
+            instances.push(Instance {
+                name: format!("{}x{}-{}", size, size, i),
+                start: start.container,
+                end: goal.container.clone(),
+                size,
+            });
+        }
+    }
+
+    instances
+}
+
+// Solves every instance with every registered solver and heuristic. Only the
+// in-memory A* of solver::SOLVERS is registered: uniform cost and greedy
+// search are A* with other weights, and external::astar is left out since it
+// needs a directory to write to and can fail on I/O
+pub fn run(instances: &[Instance]) -> Vec<Record> {
+    let mut records = Vec::new();
+
+    for instance in instances {
+        for &(solver_name, solver) in SOLVERS.iter() {
+            for &(heuristic_name, heuristic) in HEURISTICS.iter() {
+                let problem = Problem {
+                    start: instance.start.clone(),
+                    end: instance.end.clone(),
+                    size: instance.size,
+                    heuristic,
+                    g_weight: 1,
+                    h_weight: 1,
+                };
+
+                let start_time = Instant::now();
+                let solution = solver(&problem, &mut SearchOptions::default());
+                let time = start_time.elapsed();
+
+                records.push(Record {
+                    instance: instance.name.clone(),
+                    size: instance.size,
+                    solver: solver_name,
+                    heuristic: heuristic_name,
+                    expanded: solution.closed_states,
+                    max_states: solution.max_states,
+                    length: solution.path.len().checked_sub(1),
+                    time,
+                });
+            }
+        }
+    }
+
+    records
+}
+
+fn length(record: &Record) -> String {
+    match record.length {
+        Some(l) => l.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+pub fn print_table(records: &[Record]) {
+    println!("Benchmark suite v{}", SUITE_VERSION);
+    println!(
+        "{:<8} {:<8} {:<10} {:>8} {:>10} {:>10} {:>12}",
+        "instance", "solver", "heuristic", "length", "expanded", "max states", "time (ms)"
+    );
+
+    for r in records {
+        println!(
+            "{:<8} {:<8} {:<10} {:>8} {:>10} {:>10} {:>12.3}",
+            r.instance,
+            r.solver,
+            r.heuristic,
+            length(r),
+            r.expanded,
+            r.max_states,
+            r.time.as_secs_f64() * 1000.0
+        );
+    }
+
+    println!("-----------------");
+    for &(solver_name, _) in SOLVERS.iter() {
+        for &(heuristic_name, _) in HEURISTICS.iter() {
+            let matching = records
+                .iter()
+                .filter(|r| r.solver == solver_name && r.heuristic == heuristic_name);
+            let expanded: Statistic = matching.clone().map(|r| r.expanded).sum();
+            let time: Duration = matching.map(|r| r.time).sum();
+
+            println!(
+                " - {} / {}: {} expanded, {:.3} ms",
+                solver_name,
+                heuristic_name,
+                expanded,
+                time.as_secs_f64() * 1000.0
+            );
+        }
+    }
+}
+
+pub fn print_csv(records: &[Record]) {
+    println!("suite,instance,size,solver,heuristic,length,expanded,max_states,time_us");
+
+    for r in records {
+        println!(
+            "{},{},{},{},{},{},{},{},{}",
+            SUITE_VERSION,
+            r.instance,
+            r.size,
+            r.solver,
+            r.heuristic,
+            length(r),
+            r.expanded,
+            r.max_states,
+            r.time.as_micros()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suite_is_stable() {
+        let a = suite();
+        let b = suite();
+
+        assert_eq!(a.len(), 12);
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert_eq!(x.name, y.name);
+            assert_eq!(x.start, y.start);
+        }
+    }
+
+    #[test]
+    fn run_every_combination() {
+        let instances = suite();
+        let records = run(&instances[..1]);
+
+        assert_eq!(records.len(), SOLVERS.len() * HEURISTICS.len());
+        assert!(records.iter().all(|r| r.length.is_some()));
+    }
+}
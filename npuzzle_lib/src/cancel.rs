@@ -0,0 +1,41 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+// Shared flag asking a running solver to stop. Clones share the same flag, so
+// one can be handed to the solver and another kept to cancel from any thread.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn cancel_from_thread() {
+        let token = CancelToken::new();
+        let other = token.clone();
+
+        assert!(!token.is_cancelled());
+        thread::spawn(move || other.cancel()).join().unwrap();
+        assert!(token.is_cancelled());
+    }
+}
@@ -1,10 +1,48 @@
+use std::time::Duration;
+
+use heuristics;
+use solver::Budget;
+use solver::Search;
 use types::Atom;
+use types::Heuristic;
+use types::Problem;
 use types::Puzzle;
+use types::Result;
+use types::SearchOptions;
+use types::Statistic;
+use types::Status;
 
 use util::find_empty_pos;
 use util::xy;
 
-fn inversions(puzzle: &Puzzle) -> Atom {
+// Side of a solvability check: for even sizes the row of the blank, counted
+// from 0 at the top, adds to the inversions
+pub struct Parity {
+    pub inversions: u64,
+    pub blank_row: Option<Atom>,
+}
+
+impl Parity {
+    pub fn is_even(&self) -> bool {
+        (self.inversions + self.blank_row.unwrap_or(0) as u64).is_multiple_of(2)
+    }
+}
+
+// Why a start can or cannot reach its goal, with two tiles whose swap would
+// change the answer when it cannot
+pub struct Explanation {
+    pub start: Parity,
+    pub goal: Parity,
+    pub swap: Option<(Atom, Atom)>,
+}
+
+impl Explanation {
+    pub fn is_solvable(&self) -> bool {
+        self.start.is_even() == self.goal.is_even()
+    }
+}
+
+fn inversions(puzzle: &Puzzle) -> u64 {
     let mut inversions = 0;
     for i in 0..puzzle.len() - 1 {
         for j in i + 1..puzzle.len() {
@@ -21,20 +59,231 @@ fn inversions(puzzle: &Puzzle) -> Atom {
     inversions
 }
 
+fn parity(puzzle: &Puzzle, size: Atom) -> Parity {
+    Parity {
+        inversions: inversions(puzzle),
+        blank_row: if size.is_multiple_of(2) {
+            Some(xy(find_empty_pos(puzzle), size).1)
+        } else {
+            None
+        },
+    }
+}
+
 pub fn is_solvable(puzzle: &Puzzle, goal: &Puzzle, size: Atom) -> bool {
-    let mut inv = inversions(puzzle);
-    let mut g_inv = inversions(goal);
+    parity(puzzle, size).is_even() == parity(goal, size).is_even()
+}
+
+// Swapping two tiles flips the parity without moving the blank. The swap
+// chosen puts both tiles in place when possible, else at least one of them.
+fn parity_fix(puzzle: &Puzzle, goal: &Puzzle) -> Option<(Atom, Atom)> {
+    let mut goal_pos = vec![0; goal.len()];
+    let mut one_placed = None;
+
+    for (i, &v) in goal.iter().enumerate() {
+        goal_pos[v as usize] = i;
+    }
+    for (i, &tile) in puzzle.iter().enumerate() {
+        if tile == 0 || tile == goal[i] {
+            continue;
+        }
+
+        let j = goal_pos[tile as usize];
+        if puzzle[j] == 0 {
+            continue;
+        }
+        if puzzle[j] == goal[i] {
+            return Some((tile.min(puzzle[j]), tile.max(puzzle[j])));
+        }
+        one_placed = one_placed.or(Some((tile.min(puzzle[j]), tile.max(puzzle[j]))));
+    }
+
+    one_placed.or_else(|| {
+        let mut tiles = puzzle.iter().filter(|&&t| t != 0);
+
+        match (tiles.next(), tiles.next()) {
+            (Some(&a), Some(&b)) => Some((a.min(b), a.max(b))),
+            _ => None,
+        }
+    })
+}
+
+pub fn explain(puzzle: &Puzzle, goal: &Puzzle, size: Atom) -> Explanation {
+    let mut explanation = Explanation {
+        start: parity(puzzle, size),
+        goal: parity(goal, size),
+        swap: None,
+    };
+
+    if !explanation.is_solvable() {
+        explanation.swap = parity_fix(puzzle, goal);
+    }
+
+    explanation
+}
+
+fn format_parity(name: &str, parity: &Parity) -> String {
+    let parity_name = if parity.is_even() { "even" } else { "odd" };
+
+    match parity.blank_row {
+        Some(row) => format!(
+            " - {} : {} inversions + blank on row {} (from 0 at the top) = {}\n",
+            name,
+            parity.inversions,
+            row,
+            parity_name
+        ),
+        None => format!(" - {} : {} inversions = {}\n", name, parity.inversions, parity_name),
+    }
+}
+
+pub fn format_explanation(explanation: &Explanation) -> String {
+    let mut s = format_parity("start", &explanation.start);
+
+    s.push_str(&format_parity("goal", &explanation.goal));
+    if explanation.is_solvable() {
+        s.push_str(" - Parities match, the goal can be reached\n");
+    } else {
+        s.push_str(" - Parities differ, no sequence of moves reaches the goal\n");
+    }
+    if let Some((a, b)) = explanation.swap {
+        s.push_str(&format!(" - Swap tiles {} and {} to make it solvable\n", a, b));
+    }
+
+    s
+}
+
+// Weight of the heuristic and budget of the search behind the upper bound,
+// the weight trading a longer path for a search that ends on most 4x4 puzzles
+pub const UPPER_BOUND_WEIGHT: u64 = 2;
+pub const UPPER_BOUND_EXPANDED: Statistic = 200_000;
+pub const UPPER_BOUND_TIME: Duration = Duration::from_secs(2);
+
+// What can be told of a puzzle without solving it optimally. Cycles are those
+// of the permutation taking start to goal, each tile being followed by the one
+// standing where it has to go; tiles already in place are left out.
+pub struct Analysis {
+    pub explanation: Explanation,
+    pub cycles: Vec<Vec<Atom>>,
+    pub misplaced: usize,
+    pub manhattan: Result,
+    pub linear_conflicts: Result,
+    pub upper_bound: Option<usize>,
+}
+
+fn goal_positions(goal: &Puzzle) -> Vec<usize> {
+    let mut positions = vec![0; goal.len()];
+
+    for (i, &v) in goal.iter().enumerate() {
+        positions[v as usize] = i;
+    }
+
+    positions
+}
+
+fn cycles(puzzle: &Puzzle, goal_pos: &[usize]) -> Vec<Vec<Atom>> {
+    let mut visited = vec![false; puzzle.len()];
+    let mut cycles = Vec::new();
 
-    if size % 2 == 0 {
-        inv += xy(find_empty_pos(&puzzle), size).1;
-        g_inv += xy(find_empty_pos(&goal), size).1;
+    for start in 0..puzzle.len() {
+        let mut cycle = Vec::new();
+        let mut i = start;
+
+        while !visited[i] {
+            visited[i] = true;
+            cycle.push(puzzle[i]);
+            i = goal_pos[puzzle[i] as usize];
+        }
+        if cycle.len() > 1 {
+            cycles.push(cycle);
+        }
+    }
+
+    cycles
+}
+
+// Length found by a weighted search, None when it runs out of budget
+fn upper_bound(puzzle: &Puzzle, goal: &Puzzle, size: Atom) -> Option<usize> {
+    let problem = Problem {
+        start: puzzle.clone(),
+        end: goal.clone(),
+        size,
+        heuristic: Heuristic::Conflicts,
+        g_weight: 1,
+        h_weight: UPPER_BOUND_WEIGHT,
+    };
+    let budget = Budget {
+        expanded: Some(UPPER_BOUND_EXPANDED),
+        time: Some(UPPER_BOUND_TIME),
+    };
+    let mut search = Search::new(&problem);
+
+    search.run(&mut SearchOptions::default(), &budget);
+    let solution = search.solution();
+    match solution.status {
+        Status::Solved => Some(solution.path.len() - 1),
+        _ => None,
+    }
+}
+
+pub fn analyze(puzzle: &Puzzle, goal: &Puzzle, size: Atom) -> Analysis {
+    let goal_pos = goal_positions(goal);
+    let explanation = explain(puzzle, goal, size);
+    let upper_bound = if explanation.is_solvable() {
+        upper_bound(puzzle, goal, size)
+    } else {
+        None
+    };
+
+    Analysis {
+        cycles: cycles(puzzle, &goal_pos),
+        misplaced: puzzle.iter().zip(goal).filter(|(&a, &b)| a != 0 && a != b).count(),
+        manhattan: heuristics::manhattan(puzzle, goal, size),
+        linear_conflicts: heuristics::linear_conflicts(puzzle, goal, size),
+        upper_bound,
+        explanation,
+    }
+}
+
+pub fn format_analysis(analysis: &Analysis) -> String {
+    let cycles: Vec<String> = analysis
+        .cycles
+        .iter()
+        .map(|c| {
+            let tiles: Vec<String> = c.iter().map(|t| t.to_string()).collect();
+            format!("({})", tiles.join(" "))
+        })
+        .collect();
+    let longest = analysis.cycles.iter().map(|c| c.len()).max().unwrap_or(0);
+
+    let mut s = String::from("Analysis\n");
+    s.push_str(&format!(" - Misplaced tiles : {}\n", analysis.misplaced));
+    s.push_str(&format!(
+        " - Cycles : {} (longest {}) {}\n",
+        analysis.cycles.len(),
+        longest,
+        cycles.join(" ")
+    ));
+    s.push_str(&format!(" - Manhattan lower bound : {}\n", analysis.manhattan));
+    s.push_str(&format!(" - Linear conflicts lower bound : {}\n", analysis.linear_conflicts));
+    if !analysis.explanation.is_solvable() {
+        s.push_str(" - Not solvable\n");
+        s.push_str(&format_explanation(&analysis.explanation));
+        return s;
+    }
+    match analysis.upper_bound {
+        Some(length) => s.push_str(&format!(" - Weighted A* upper bound : {}\n", length)),
+        None => s.push_str(" - Weighted A* upper bound : unknown, the search ran out of budget\n"),
     }
 
-    return inv % 2 == g_inv % 2;
+    s
 }
 
 #[cfg(test)]
 mod tests {
+    use solver;
+    use types::Heuristic;
+    use types::Problem;
     use types::Puzzle;
 
     #[test]
@@ -93,4 +342,119 @@ mod tests {
         assert!(super::is_solvable(&a, &b, 3));
         assert!(super::is_solvable(&b, &a, 3));
     }
+
+    #[test]
+    fn explain_odd_size() {
+        let a: Puzzle = vec![1, 2, 3, 4, 5, 6, 8, 7, 0];
+        let b: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
+        let explanation = super::explain(&a, &b, 3);
+
+        assert!(!explanation.is_solvable());
+        assert_eq!(explanation.start.inversions, 1);
+        assert_eq!(explanation.start.blank_row, None);
+        assert_eq!(explanation.goal.inversions, 0);
+        assert_eq!(explanation.swap, Some((7, 8)));
+        assert_eq!(
+            super::format_explanation(&explanation),
+            concat!(
+                " - start : 1 inversions = odd\n",
+                " - goal : 0 inversions = even\n",
+                " - Parities differ, no sequence of moves reaches the goal\n",
+                " - Swap tiles 7 and 8 to make it solvable\n"
+            )
+        );
+    }
+
+    #[test]
+    fn explain_even_size() {
+        let a: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15, 14, 0];
+        let b: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0];
+        let explanation = super::explain(&a, &b, 4);
+
+        assert!(!explanation.is_solvable());
+        assert_eq!(explanation.start.blank_row, Some(3));
+        assert_eq!(explanation.swap, Some((14, 15)));
+        assert!(super::explain(&b, &b, 4).swap.is_none());
+    }
+
+    #[test]
+    fn explain_swap_makes_it_solvable() {
+        let goal: Puzzle = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
+        let starts: [Puzzle; 3] = [
+            vec![2, 1, 3, 8, 0, 4, 7, 6, 5],
+            vec![1, 2, 3, 4, 0, 8, 6, 5, 7],
+            vec![2, 3, 4, 8, 0, 1, 7, 6, 5],
+        ];
+
+        for start in starts.iter() {
+            let explanation = super::explain(start, &goal, 3);
+            let (a, b) = explanation.swap.unwrap();
+            let fixed: Puzzle = start
+                .iter()
+                .map(|&t| if t == a { b } else if t == b { a } else { t })
+                .collect();
+
+            assert!(!explanation.is_solvable());
+            assert!(super::is_solvable(&fixed, &goal, 3));
+        }
+    }
+
+    #[test]
+    fn inversions_large() {
+        let v: Puzzle = (0..10000).rev().collect();
+
+        assert_eq!(super::inversions(&v), 9998 * 9999 / 2);
+    }
+
+    #[test]
+    fn analyze_cycles() {
+        let goal: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
+        let start: Puzzle = vec![2, 3, 1, 4, 5, 6, 7, 0, 8];
+        let analysis = super::analyze(&start, &goal, 3);
+
+        assert_eq!(analysis.cycles, vec![vec![2, 3, 1], vec![0, 8]]);
+        assert_eq!(analysis.misplaced, 4);
+    }
+
+    #[test]
+    fn analyze_bounds() {
+        let goal: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
+        // 3 2 1 on the first row: two of them have to leave it
+        let start: Puzzle = vec![3, 2, 1, 4, 5, 6, 7, 8, 0];
+        let analysis = super::analyze(&start, &goal, 3);
+
+        assert_eq!(analysis.manhattan, 4);
+        assert_eq!(analysis.linear_conflicts, 8);
+        assert!(analysis.upper_bound.is_none());
+        assert!(!analysis.explanation.is_solvable());
+
+        let start: Puzzle = vec![1, 2, 3, 4, 5, 6, 0, 7, 8];
+        let analysis = super::analyze(&start, &goal, 3);
+
+        assert_eq!((analysis.manhattan, analysis.linear_conflicts), (2, 2));
+        assert_eq!(analysis.upper_bound, Some(2));
+        assert!(super::format_analysis(&analysis).contains(" - Weighted A* upper bound : 2\n"));
+    }
+
+    #[test]
+    fn analyze_bounds_bracket_optimal() {
+        let goal: Puzzle = vec![1, 2, 3, 8, 0, 4, 7, 6, 5];
+        let start: Puzzle = vec![8, 1, 2, 0, 4, 3, 7, 6, 5];
+        let analysis = super::analyze(&start, &goal, 3);
+        let optimal = solver::astar(&Problem {
+            start: start.clone(),
+            end: goal.clone(),
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 0,
+        })
+        .path
+        .len() as u64
+            - 1;
+
+        assert!(analysis.manhattan <= analysis.linear_conflicts);
+        assert!(analysis.linear_conflicts <= optimal);
+        assert!(analysis.upper_bound.unwrap() as u64 >= optimal);
+    }
 }
@@ -0,0 +1,43 @@
+use std::time::Duration;
+#[cfg(feature = "native")]
+use std::time::SystemTime;
+#[cfg(feature = "native")]
+use std::time::UNIX_EPOCH;
+
+use types::Statistic;
+
+// Time elapsed since some fixed origin. Solvers only look at differences, so
+// any source will do, such as performance.now() in a browser where the system
+// clock is not available.
+pub type Clock = fn() -> Duration;
+
+#[cfg(feature = "native")]
+pub fn system() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+// Never moves: elapsed times read zero and time budgets never run out
+pub fn stopped() -> Duration {
+    Duration::from_secs(0)
+}
+
+#[cfg(feature = "native")]
+pub static DEFAULT: Clock = system;
+#[cfg(not(feature = "native"))]
+pub static DEFAULT: Clock = stopped;
+
+// Expansions between two readings of the clock by a search, reading it on
+// every expansion would slow the search down
+const SAMPLE_EVERY: Statistic = 64;
+
+// Whether a search that made `expanded` expansions reads the clock now
+pub fn due(expanded: Statistic) -> bool {
+    expanded.is_multiple_of(SAMPLE_EVERY)
+}
+
+// Time since `start`, zero if the clock went backwards
+pub fn since(clock: Clock, start: Duration) -> Duration {
+    clock().checked_sub(start).unwrap_or_default()
+}
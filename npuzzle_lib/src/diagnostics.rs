@@ -0,0 +1,429 @@
+use input_parser::ERR_DUPLICATED_VALUE;
+use input_parser::ERR_INVALID_ELMT;
+use input_parser::ERR_NUMBER_INVALID;
+use input_parser::ERR_NUMBER_OF_LINES;
+use input_parser::ERR_NUMBER_PER_LINE;
+use input_parser::ERR_SIZE;
+use input_parser::ERR_SIZE_INVALID;
+use input_parser::ERR_SIZE_SYNTAX;
+use types::Atom;
+use types::MAX_PUZZLE_SIZE;
+
+pub static ERR_SIZE_MISSING: &str = "Size declaration is missing";
+pub static ERR_MISSING_VALUES: &str = "The following numbers are missing";
+
+// Most missing numbers listed in a single diagnostic
+const MAX_LISTED: usize = 10;
+
+// Characters of a line, counted from 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub text: String,
+}
+
+// One problem of a puzzle file. The primary label is underlined with `^`,
+// secondary ones, such as the first place of a duplicated number, with `-`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub primary: Option<Label>,
+    pub secondary: Vec<Label>,
+}
+
+impl Diagnostic {
+    fn new(message: String, span: Span, text: String) -> Diagnostic {
+        Diagnostic {
+            message,
+            primary: Some(Label { span, text }),
+            secondary: Vec::new(),
+        }
+    }
+}
+
+struct Word<'a> {
+    text: &'a str,
+    span: Span,
+}
+
+// Words of a line up to its comment
+fn words<'a>(line_nb: usize, line: &'a str) -> Vec<Word<'a>> {
+    let content = line.split('#').next().unwrap_or("");
+    let mut words = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut columns = 0;
+
+    for (column, (i, c)) in content.char_indices().enumerate() {
+        if !c.is_whitespace() {
+            start = start.or(Some((i, column)));
+        } else if let Some((begin, first)) = start.take() {
+            words.push(Word {
+                text: &content[begin..i],
+                span: Span {
+                    line: line_nb,
+                    column: first + 1,
+                    len: column - first,
+                },
+            });
+        }
+        columns = column + 1;
+    }
+    if let Some((begin, first)) = start {
+        words.push(Word {
+            text: &content[begin..],
+            span: Span {
+                line: line_nb,
+                column: first + 1,
+                len: columns - first,
+            },
+        });
+    }
+
+    words
+}
+
+// From the start of the first word to the end of the last one
+fn cover(words: &[Word]) -> Span {
+    let first = words[0].span;
+    let last = words[words.len() - 1].span;
+
+    Span {
+        line: first.line,
+        column: first.column,
+        len: last.column + last.len - first.column,
+    }
+}
+
+fn check_size(words: &[Word], diagnostics: &mut Vec<Diagnostic>) -> Option<Atom> {
+    if words.len() > 1 {
+        diagnostics.push(Diagnostic::new(
+            ERR_SIZE_SYNTAX.to_string(),
+            cover(&words[1..]),
+            "the size must be alone on its line".to_string(),
+        ));
+    }
+
+    let word = &words[0];
+    let (message, text) = match word.text.parse::<Atom>() {
+        Ok(size) if size < 3 => (ERR_SIZE, "too small".to_string()),
+        Ok(size) if size > MAX_PUZZLE_SIZE => (ERR_SIZE_INVALID, format!("at most {}", MAX_PUZZLE_SIZE)),
+        Ok(size) => return Some(size),
+        Err(_) => (ERR_SIZE_INVALID, "not a size".to_string()),
+    };
+    diagnostics.push(Diagnostic::new(message.to_string(), word.span, text));
+
+    None
+}
+
+fn check_row(
+    words: &[Word],
+    size: Atom,
+    seen: &mut [Option<Span>],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let max_number = size as usize * size as usize;
+
+    if words.len() != size as usize {
+        let span = if words.len() > size as usize {
+            cover(&words[size as usize..])
+        } else {
+            cover(words)
+        };
+
+        diagnostics.push(Diagnostic::new(
+            ERR_NUMBER_PER_LINE.to_string(),
+            span,
+            format!("expected {} numbers, found {}", size, words.len()),
+        ));
+    }
+
+    for word in words {
+        let value = match word.text.parse::<Atom>() {
+            Ok(v) => v as usize,
+            Err(_) => {
+                diagnostics.push(Diagnostic::new(
+                    ERR_NUMBER_INVALID.to_string(),
+                    word.span,
+                    "not a number".to_string(),
+                ));
+                continue;
+            }
+        };
+
+        if value >= max_number {
+            diagnostics.push(Diagnostic::new(
+                format!("{} {} : {}", ERR_INVALID_ELMT, max_number - 1, value),
+                word.span,
+                "too large".to_string(),
+            ));
+        } else if let Some(first) = seen[value] {
+            let mut diagnostic = Diagnostic::new(
+                format!("{} : {}", ERR_DUPLICATED_VALUE, value),
+                word.span,
+                "duplicated here".to_string(),
+            );
+
+            diagnostic.secondary.push(Label {
+                span: first,
+                text: "first found here".to_string(),
+            });
+            diagnostics.push(diagnostic);
+        } else {
+            seen[value] = Some(word.span);
+        }
+    }
+}
+
+// Every problem of a puzzle in the 42 format, in the order of the file. When
+// the size cannot be read, the width of the first row stands for it so that
+// the rows are still checked.
+pub fn check(content: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let lines: Vec<Vec<Word>> = content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| words(i + 1, l))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let (size_line, rows) = match lines.split_first() {
+        Some(l) => l,
+        None => {
+            diagnostics.push(Diagnostic {
+                message: ERR_SIZE_MISSING.to_string(),
+                primary: None,
+                secondary: Vec::new(),
+            });
+            return diagnostics;
+        }
+    };
+
+    let size = match check_size(size_line, &mut diagnostics) {
+        Some(s) => s,
+        None => match rows.first().map(|r| r.len() as Atom) {
+            Some(s) if s >= 3 && s <= MAX_PUZZLE_SIZE => s,
+            _ => return diagnostics,
+        },
+    };
+
+    let mut seen = vec![None; size as usize * size as usize];
+    for row in rows {
+        check_row(row, size, &mut seen, &mut diagnostics);
+    }
+
+    if rows.len() != size as usize {
+        let (span, text) = match rows.get(size as usize) {
+            Some(extra) => (cover(extra), format!("expected {} rows, this is row {}", size, size as usize + 1)),
+            None => (
+                cover(rows.last().unwrap_or(size_line)),
+                format!("expected {} rows, found {}", size, rows.len()),
+            ),
+        };
+
+        diagnostics.push(Diagnostic::new(ERR_NUMBER_OF_LINES.to_string(), span, text));
+    }
+
+    let missing: Vec<String> = seen
+        .iter()
+        .enumerate()
+        .filter(|s| s.1.is_none())
+        .map(|s| s.0.to_string())
+        .collect();
+    if !missing.is_empty() {
+        let mut message = format!("{} : {}", ERR_MISSING_VALUES, missing[..missing.len().min(MAX_LISTED)].join(", "));
+
+        if missing.len() > MAX_LISTED {
+            message.push_str(&format!(" and {} more", missing.len() - MAX_LISTED));
+        }
+        diagnostics.push(Diagnostic {
+            message,
+            primary: None,
+            secondary: Vec::new(),
+        });
+    }
+
+    // stable, so that problems of a same word keep the order they were found in
+    diagnostics.sort_by_key(|d| match d.primary {
+        Some(ref l) => (l.span.line, l.span.column),
+        None => (usize::MAX, 0),
+    });
+
+    diagnostics
+}
+
+fn underline(s: &mut String, source: &[&str], gutter: usize, label: &Label, mark: char) {
+    let line = source.get(label.span.line - 1).cloned().unwrap_or("");
+    // tabs would shift the underline
+    let line: String = line.chars().map(|c| if c == '\t' { ' ' } else { c }).collect();
+
+    s.push_str(&format!("{:>2$} | {}\n", label.span.line, line.trim_end(), gutter));
+    s.push_str(&format!(
+        "{:>4$} | {}{} {}\n",
+        "",
+        " ".repeat(label.span.column - 1),
+        mark.to_string().repeat(label.span.len.max(1)),
+        label.text,
+        gutter
+    ));
+}
+
+// Shows a diagnostic the way rustc does, with the lines it points at
+pub fn render(diagnostic: &Diagnostic, content: &str, filename: &str) -> String {
+    let mut s = format!("error: {}\n", diagnostic.message);
+    let primary = match diagnostic.primary {
+        Some(ref p) => p,
+        None => {
+            s.push_str(&format!(" --> {}\n", filename));
+            return s;
+        }
+    };
+
+    let mut labels: Vec<(&Label, char)> = vec![(primary, '^')];
+    labels.extend(diagnostic.secondary.iter().map(|l| (l, '-')));
+    labels.sort_by_key(|l| (l.0.span.line, l.0.span.column));
+
+    let source: Vec<&str> = content.lines().collect();
+    let gutter = labels.iter().map(|l| l.0.span.line.to_string().len()).max().unwrap_or(1);
+
+    s.push_str(&format!(
+        "{:>4$}--> {}:{}:{}\n",
+        "",
+        filename,
+        primary.span.line,
+        primary.span.column,
+        gutter
+    ));
+    s.push_str(&format!("{:>1$} |\n", "", gutter));
+    for (label, mark) in labels {
+        underline(&mut s, &source, gutter, label, mark);
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(content: &str) -> Vec<String> {
+        check(content).into_iter().map(|d| d.message).collect()
+    }
+
+    #[test]
+    fn correct_puzzle() {
+        assert!(check("# comment\n3\n1 2 3 # row\n8 0 4\n\n7 6 5\n").is_empty());
+    }
+
+    #[test]
+    fn every_problem() {
+        let content = "3\n1 2 3\n4 5 7 9\n7 x 0\n";
+        let diagnostics = check(content);
+
+        assert_eq!(
+            diagnostics.iter().map(|d| &d.message as &str).collect::<Vec<_>>(),
+            vec![
+                ERR_NUMBER_PER_LINE.to_string(),
+                format!("{} 8 : 9", ERR_INVALID_ELMT),
+                format!("{} : 7", ERR_DUPLICATED_VALUE),
+                ERR_NUMBER_INVALID.to_string(),
+                format!("{} : 6, 8", ERR_MISSING_VALUES),
+            ]
+        );
+        assert_eq!(
+            diagnostics[2].primary.as_ref().unwrap().span,
+            Span { line: 4, column: 1, len: 1 }
+        );
+        assert_eq!(
+            diagnostics[2].secondary[0].span,
+            Span { line: 3, column: 5, len: 1 }
+        );
+    }
+
+    #[test]
+    fn size_problems() {
+        assert_eq!(messages(""), vec![ERR_SIZE_MISSING.to_string()]);
+        assert_eq!(messages("2\n"), vec![ERR_SIZE.to_string()]);
+        assert_eq!(messages("65535\n"), vec![ERR_SIZE_INVALID.to_string()]);
+        assert_eq!(
+            messages("3 3\n1 2 3\n8 0 4\n7 6 5\n"),
+            vec![ERR_SIZE_SYNTAX.to_string()]
+        );
+        // the rows are still checked against the width of the first one
+        assert_eq!(
+            messages("toto\n1 2 3\n4 5 6\n7 8 8\n"),
+            vec![
+                ERR_SIZE_INVALID.to_string(),
+                format!("{} : 8", ERR_DUPLICATED_VALUE),
+                format!("{} : 0", ERR_MISSING_VALUES),
+            ]
+        );
+    }
+
+    #[test]
+    fn row_count() {
+        let missing = check("4\n1 2 3 4\n5 6 7 8\n9 10 11 12\n");
+
+        assert_eq!(missing[0].message, ERR_NUMBER_OF_LINES);
+        assert_eq!(missing[0].primary.as_ref().unwrap().span.line, 4);
+        assert_eq!(
+            missing[1].message,
+            format!("{} : 0, 13, 14, 15", ERR_MISSING_VALUES)
+        );
+
+        let extra = check("3\n1 2 3\n8 0 4\n7 6 5\n9 10 11\n");
+        let rows = extra.iter().find(|d| d.message == ERR_NUMBER_OF_LINES).unwrap();
+        assert_eq!(
+            rows.primary.as_ref().unwrap().span,
+            Span { line: 5, column: 1, len: 7 }
+        );
+    }
+
+    #[test]
+    fn missing_values_are_capped() {
+        let diagnostics = check("5\n1 2 3 4 5\n");
+
+        assert_eq!(
+            diagnostics[1].message,
+            format!("{} : 0, 6, 7, 8, 9, 10, 11, 12, 13, 14 and 10 more", ERR_MISSING_VALUES)
+        );
+    }
+
+    #[test]
+    fn render_snippets() {
+        let content = "3\n1 2 3\n4 5 7\n7 8 0\n";
+        let diagnostics = check(content);
+
+        assert_eq!(
+            render(&diagnostics[0], content, "puzzle"),
+            format!(
+                "error: {} : 7\n --> puzzle:4:1\n  |\n3 | 4 5 7\n  |     - first found here\n4 | 7 8 0\n  | ^ duplicated here\n",
+                ERR_DUPLICATED_VALUE
+            )
+        );
+        assert_eq!(
+            render(&diagnostics[1], content, "puzzle"),
+            format!("error: {} : 6\n --> puzzle\n", ERR_MISSING_VALUES)
+        );
+    }
+
+    #[test]
+    fn render_long_rows() {
+        let content = "3\n1 2 3\n8\t0 4 10 11\n7 6 5\n";
+        let rendered = render(&check(content)[0], content, "p");
+
+        assert_eq!(
+            rendered,
+            format!(
+                "error: {}\n --> p:3:7\n  |\n3 | 8 0 4 10 11\n  |       ^^^^^ expected 3 numbers, found 5\n",
+                ERR_NUMBER_PER_LINE
+            )
+        );
+    }
+}
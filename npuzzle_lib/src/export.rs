@@ -0,0 +1,253 @@
+use std::fmt::Write;
+
+use game::moved_tile;
+use types::Atom;
+use types::Puzzle;
+use types::Solution;
+
+const CELL: usize = 40;
+const GAP: usize = 20;
+const LABEL: usize = 20;
+const FRAMES_PER_ROW: usize = 8;
+
+const TILE_COLOR: &str = "#f4d58d";
+const MOVED_COLOR: &str = "#e07a5f";
+const BORDER_COLOR: &str = "#3d405b";
+
+fn font_size(puzzle: &Puzzle) -> usize {
+    let digits = puzzle.iter().max().map_or(1, |v| v.to_string().len());
+
+    ::std::cmp::min(CELL / 2, CELL * 3 / (2 * digits + 1))
+}
+
+// Tiles of one board, drawn with its top left corner at (x, y)
+fn board(out: &mut String, puzzle: &Puzzle, size: Atom, moved: Option<Atom>, x: usize, y: usize) {
+    let size = size as usize;
+    let font = font_size(puzzle);
+
+    let _ = write!(
+        out,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        x,
+        y,
+        size * CELL,
+        size * CELL,
+        BORDER_COLOR
+    );
+
+    for (i, &v) in puzzle.iter().enumerate() {
+        if v == 0 {
+            continue;
+        }
+
+        let cx = x + (i % size) * CELL;
+        let cy = y + (i / size) * CELL;
+        let color = if Some(v) == moved {
+            MOVED_COLOR
+        } else {
+            TILE_COLOR
+        };
+
+        let _ = write!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\"/>",
+            cx + 2,
+            cy + 2,
+            CELL - 4,
+            CELL - 4,
+            color
+        );
+        let _ = write!(
+            out,
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\">{}</text>",
+            cx + CELL / 2,
+            cy + CELL / 2,
+            font,
+            v
+        );
+    }
+}
+
+fn svg_open(out: &mut String, width: usize, height: usize) {
+    let _ = write!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" text-anchor=\"middle\" \
+         dominant-baseline=\"central\">",
+        width, height
+    );
+}
+
+// A single board, such as the start or the goal
+pub fn board_svg(puzzle: &Puzzle, size: Atom) -> String {
+    let side = size as usize * CELL;
+    let mut out = String::new();
+
+    svg_open(&mut out, side, side);
+    board(&mut out, puzzle, size, None, 0, 0);
+    out.push_str("</svg>\n");
+
+    out
+}
+
+// Every state of a path side by side, the moved tile highlighted
+pub fn path_svg(path: &[Puzzle], size: Atom) -> String {
+    let side = size as usize * CELL;
+    let columns = path.len().clamp(1, FRAMES_PER_ROW);
+    let rows = path.len().div_ceil(columns);
+    let mut out = String::new();
+
+    svg_open(
+        &mut out,
+        columns * (side + GAP) + GAP,
+        rows * (side + GAP + LABEL) + GAP,
+    );
+
+    for (step, puzzle) in path.iter().enumerate() {
+        let x = GAP + (step % columns) * (side + GAP);
+        let y = GAP + (step / columns) * (side + GAP + LABEL);
+        let moved = if step > 0 {
+            moved_tile(&path[step - 1], puzzle)
+        } else {
+            None
+        };
+
+        let _ = write!(
+            out,
+            "<text x=\"{}\" y=\"{}\" font-size=\"14\">{}</text>",
+            x + side / 2,
+            y + LABEL / 2,
+            if step == 0 {
+                "start".to_string()
+            } else {
+                step.to_string()
+            }
+        );
+        board(&mut out, puzzle, size, moved, x, y + LABEL);
+    }
+    out.push_str("</svg>\n");
+
+    out
+}
+
+// Self-contained page showing one state at a time, chosen with a slider
+pub fn path_html(path: &[Puzzle], size: Atom) -> String {
+    let frames: Vec<String> = path
+        .iter()
+        .map(|p| {
+            let values: Vec<String> = p.iter().map(|v| v.to_string()).collect();
+            format!("[{}]", values.join(","))
+        })
+        .collect();
+    let font = path.first().map_or(CELL / 2, font_size);
+    let mut out = String::new();
+
+    let _ = write!(
+        out,
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>rustaquin - {size}x{size} solution in {moves} moves</title>
+<style>
+body {{ font-family: sans-serif; text-align: center; }}
+input {{ width: 80%; }}
+</style>
+</head>
+<body>
+<p id="label"></p>
+<div id="board"></div>
+<p><input id="step" type="range" min="0" max="{moves}" value="0"></p>
+<script>
+var size = {size}, cell = {cell}, font = {font};
+var frames = [{frames}];
+function moved(step) {{
+  if (step == 0) return -1;
+  return frames[step][frames[step - 1].indexOf(0)];
+}}
+function draw(step) {{
+  var side = size * cell, m = moved(step);
+  var s = '<svg xmlns="http://www.w3.org/2000/svg" width="' + side + '" height="' + side +
+    '" font-family="sans-serif" text-anchor="middle" dominant-baseline="central">' +
+    '<rect width="' + side + '" height="' + side + '" fill="{border}"/>';
+  frames[step].forEach(function (v, i) {{
+    if (v == 0) return;
+    var x = (i % size) * cell, y = Math.floor(i / size) * cell;
+    s += '<rect x="' + (x + 2) + '" y="' + (y + 2) + '" width="' + (cell - 4) + '" height="' +
+      (cell - 4) + '" rx="4" fill="' + (v == m ? '{moved_color}' : '{tile}') + '"/>' +
+      '<text x="' + (x + cell / 2) + '" y="' + (y + cell / 2) + '" font-size="' + font + '">' +
+      v + '</text>';
+  }});
+  document.getElementById('board').innerHTML = s + '</svg>';
+  document.getElementById('label').textContent = 'Step ' + step + ' / ' + (frames.length - 1);
+}}
+var slider = document.getElementById('step');
+slider.oninput = function () {{ draw(+slider.value); }};
+draw(0);
+</script>
+</body>
+</html>
+"#,
+        size = size,
+        moves = path.len().saturating_sub(1),
+        cell = CELL,
+        font = font,
+        frames = frames.join(","),
+        border = BORDER_COLOR,
+        moved_color = MOVED_COLOR,
+        tile = TILE_COLOR
+    );
+
+    out
+}
+
+pub fn solution_svg(s: &Solution) -> String {
+    path_svg(&s.path, s.problem.size)
+}
+
+pub fn solution_html(s: &Solution) -> String {
+    path_html(&s.path, s.problem.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use game::apply_moves;
+    use generator;
+
+    #[test]
+    fn board_svg_tiles() {
+        let svg = board_svg(&generator::snail(3), 3);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<text").count(), 8);
+    }
+
+    #[test]
+    fn path_svg_highlight() {
+        let start: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 0, 8];
+        let path = apply_moves(&start, 3, "RLL").unwrap();
+        let svg = path_svg(&path, 3);
+
+        assert_eq!(svg.matches(MOVED_COLOR).count(), 3);
+        assert_eq!(svg.matches("<text").count(), 4 * 9);
+    }
+
+    #[test]
+    fn path_svg_large() {
+        let svg = path_svg(&[generator::snail(100)], 100);
+
+        assert!(svg.contains(">9999<"));
+    }
+
+    #[test]
+    fn path_html_frames() {
+        let start: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 0, 8];
+        let html = path_html(&apply_moves(&start, 3, "L").unwrap(), 3);
+
+        assert!(html.contains("var frames = [[1,2,3,4,5,6,7,0,8],[1,2,3,4,5,6,7,8,0]];"));
+        assert!(html.contains("max=\"1\""));
+        assert!(!html.contains("src="));
+    }
+}
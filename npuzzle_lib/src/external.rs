@@ -0,0 +1,714 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::BinaryHeap;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::mem;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering as AtomicOrdering;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use clock;
+use solver::neighbors;
+use types::Atom;
+use types::Problem;
+use types::Puzzle;
+use types::SearchOptions;
+use types::Solution;
+use types::Statistic;
+use types::Status;
+
+use util::find_empty_pos;
+
+// A record is the state (big endian tiles, so that byte order is state order)
+// followed by the blank position of its parent, or NO_PARENT for the root.
+type Record = Vec<u8>;
+
+const NO_PARENT: Atom = Atom::MAX;
+
+// Tells apart the workspaces of the searches of a same process
+static WORKSPACES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct DiskConfig {
+    pub temp_dir: PathBuf,
+    pub memory: usize,
+}
+
+impl Default for DiskConfig {
+    fn default() -> DiskConfig {
+        DiskConfig {
+            temp_dir: env::temp_dir(),
+            memory: 256 * 1024 * 1024,
+        }
+    }
+}
+
+fn encode(puzzle: &Puzzle, parent: Atom) -> Record {
+    let mut record = Vec::with_capacity(puzzle.len() * 2 + 2);
+
+    for v in puzzle {
+        record.push((v >> 8) as u8);
+        record.push(*v as u8);
+    }
+    record.push((parent >> 8) as u8);
+    record.push(parent as u8);
+
+    record
+}
+
+fn decode(record: &[u8]) -> (Puzzle, Atom) {
+    let atoms: Vec<Atom> = record
+        .chunks(2)
+        .map(|c| (c[0] as Atom) << 8 | c[1] as Atom)
+        .collect();
+    let (parent, puzzle) = atoms.split_last().unwrap();
+
+    (puzzle.to_vec(), *parent)
+}
+
+// Temporary directory holding every run and segment, removed on drop
+struct Workspace {
+    dir: PathBuf,
+    record_len: usize,
+    files: usize,
+}
+
+impl Workspace {
+    fn create(config: &DiskConfig, size: Atom) -> io::Result<Workspace> {
+        let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => d.subsec_nanos(),
+            Err(_) => 0,
+        };
+        let id = WORKSPACES.fetch_add(1, AtomicOrdering::Relaxed);
+        let dir = config
+            .temp_dir
+            .join(format!("rustaquin-{}-{}-{}", process::id(), id, nanos));
+
+        // a directory that already exists belongs to another search, which
+        // would remove it from under this one when done
+        fs::create_dir_all(&config.temp_dir)?;
+        fs::create_dir(&dir)?;
+
+        Ok(Workspace {
+            dir,
+            record_len: size as usize * size as usize * 2 + 2,
+            files: 0,
+        })
+    }
+
+    fn state_len(&self) -> usize {
+        self.record_len - 2
+    }
+
+    fn next_path(&mut self) -> PathBuf {
+        self.files += 1;
+        self.dir.join(format!("{}.bin", self.files))
+    }
+
+    // Records held in memory, taking record_len bytes each, plus the index
+    // sorted when they are spilled
+    fn budget(&self, memory: usize) -> usize {
+        ::std::cmp::max(memory / (self.record_len + mem::size_of::<usize>()), 1)
+    }
+
+    // Sorts and deduplicates records laid back to back, then writes them as a
+    // single run
+    fn write_run(&mut self, records: &mut Vec<u8>) -> io::Result<PathBuf> {
+        let record_len = self.record_len;
+        let state_len = self.state_len();
+        let path = self.next_path();
+        let state = |i: usize| &records[i * record_len..i * record_len + state_len];
+
+        let mut order: Vec<usize> = (0..records.len() / record_len).collect();
+        order.sort_unstable_by(|&a, &b| state(a).cmp(state(b)));
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        let mut last: Option<usize> = None;
+        for &i in order.iter() {
+            if last.is_some_and(|l| state(l) == state(i)) {
+                continue;
+            }
+            writer.write_all(&records[i * record_len..(i + 1) * record_len])?;
+            last = Some(i);
+        }
+        writer.flush()?;
+        records.clear();
+
+        Ok(path)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+struct RunReader {
+    reader: BufReader<File>,
+    record_len: usize,
+}
+
+impl RunReader {
+    fn open(path: &PathBuf, record_len: usize) -> io::Result<RunReader> {
+        Ok(RunReader {
+            reader: BufReader::new(File::open(path)?),
+            record_len,
+        })
+    }
+
+    fn next(&mut self) -> io::Result<Option<Record>> {
+        let mut record = vec![0; self.record_len];
+
+        match self.reader.read_exact(&mut record) {
+            Ok(()) => Ok(Some(record)),
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+struct Head {
+    record: Record,
+    state_len: usize,
+    run: usize,
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+// Reversed, so that the BinaryHeap pops the smallest state first
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.record[..other.state_len]
+            .cmp(&self.record[..self.state_len])
+            .then(other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// K-way merge of sorted files, yielding each state only once
+struct Merger {
+    runs: Vec<RunReader>,
+    heap: BinaryHeap<Head>,
+    state_len: usize,
+}
+
+impl Merger {
+    fn open(paths: &[PathBuf], record_len: usize) -> io::Result<Merger> {
+        let mut merger = Merger {
+            runs: Vec::new(),
+            heap: BinaryHeap::new(),
+            state_len: record_len - 2,
+        };
+
+        for path in paths {
+            merger.runs.push(RunReader::open(path, record_len)?);
+        }
+        for i in 0..merger.runs.len() {
+            merger.refill(i)?;
+        }
+
+        Ok(merger)
+    }
+
+    fn refill(&mut self, run: usize) -> io::Result<()> {
+        if let Some(record) = self.runs[run].next()? {
+            self.heap.push(Head {
+                record,
+                state_len: self.state_len,
+                run,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> io::Result<Option<Record>> {
+        let head = match self.heap.pop() {
+            Some(h) => h,
+            None => return Ok(None),
+        };
+        self.refill(head.run)?;
+
+        // drop the same state coming from other runs
+        while self
+            .heap
+            .peek()
+            .is_some_and(|h| h.record[..self.state_len] == head.record[..self.state_len])
+        {
+            let dup = self.heap.pop().unwrap();
+            self.refill(dup.run)?;
+        }
+
+        Ok(Some(head.record))
+    }
+}
+
+// Writes every candidate that is not in `closed` to a new segment, calling
+// `visit` on each of them. Returns the segment and its record count.
+fn subtract<F>(
+    ws: &mut Workspace,
+    candidates: &[PathBuf],
+    closed: &[PathBuf],
+    mut visit: F,
+) -> io::Result<(PathBuf, Statistic)>
+where
+    F: FnMut(&Record),
+{
+    let state_len = ws.state_len();
+    let path = ws.next_path();
+    let mut writer = BufWriter::new(File::create(&path)?);
+    let mut count = 0;
+
+    let mut open = Merger::open(candidates, ws.record_len)?;
+    let mut seen = Merger::open(closed, ws.record_len)?;
+    let mut seen_head = seen.next()?;
+
+    while let Some(record) = open.next()? {
+        let mut duplicate = false;
+
+        while let Some(s) = seen_head.take() {
+            match s[..state_len].cmp(&record[..state_len]) {
+                Ordering::Less => seen_head = seen.next()?,
+                Ordering::Equal => {
+                    duplicate = true;
+                    seen_head = Some(s);
+                    break;
+                }
+                Ordering::Greater => {
+                    seen_head = Some(s);
+                    break;
+                }
+            }
+        }
+
+        if !duplicate {
+            writer.write_all(&record)?;
+            visit(&record);
+            count += 1;
+        }
+    }
+    writer.flush()?;
+
+    Ok((path, count))
+}
+
+fn remove_all(paths: &mut Vec<PathBuf>) {
+    for p in paths.drain(..) {
+        let _ = fs::remove_file(p);
+    }
+}
+
+// Finds the parent blank position stored for `state` in one of `segments`
+fn lookup(ws: &Workspace, segments: &[PathBuf], state: &[u8]) -> io::Result<Option<Atom>> {
+    let state_len = ws.state_len();
+
+    for path in segments {
+        let mut reader = RunReader::open(path, ws.record_len)?;
+
+        while let Some(record) = reader.next()? {
+            match record[..state_len].cmp(state) {
+                Ordering::Less => continue,
+                Ordering::Equal => return Ok(Some(decode(&record).1)),
+                Ordering::Greater => break,
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[derive(Default)]
+struct Bucket {
+    runs: Vec<PathBuf>,
+    buffer: Vec<u8>,
+    pending: Statistic,
+    segments: Vec<PathBuf>,
+}
+
+type BucketKey = (u64, u64);
+
+// A* with delayed duplicate detection. Generated states are buffered per
+// (g, h) bucket and spilled to sorted runs whenever the memory budget is
+// exceeded. Buckets are expanded by increasing f (then g): their runs are
+// merged, and states already expanded with the same h are subtracted. Since h
+// only depends on the state, a duplicate can only live in a bucket sharing it.
+pub fn astar<'a>(
+    problem: &'a Problem,
+    config: &DiskConfig,
+    options: &mut SearchOptions,
+) -> io::Result<Solution<'a>> {
+    let start_time = (options.clock)();
+
+    let mut ws = Workspace::create(config, problem.size)?;
+    let budget = ws.budget(config.memory);
+    let goal = encode(&problem.end, 0);
+    let goal_state = &goal[..ws.state_len()];
+
+    let mut buckets: BTreeMap<BucketKey, Bucket> = BTreeMap::new();
+    let mut buffered: Statistic = 1;
+    let mut max_states: Statistic = 1;
+    let mut opened: Statistic = 1;
+    let mut closed: Statistic = 0;
+    let mut expanded: Statistic = 0;
+
+    let initial_h_result = problem.heuristic.eval(&problem.start, &problem.end, problem.size);
+    {
+        let root = buckets.entry((0, initial_h_result)).or_default();
+        root.buffer.extend(encode(&problem.start, NO_PARENT));
+        root.pending = 1;
+    }
+
+    let f = |key: &BucketKey| key.0 * problem.g_weight + key.1 * problem.h_weight;
+    let mut found: Option<BucketKey> = None;
+    let mut status = Status::Unsolvable;
+
+    // 1. Pick the pending bucket with the lowest (f, g)
+    while let Some(key) = buckets
+        .iter()
+        .filter(|&(_, b)| b.pending > 0)
+        .map(|(k, _)| *k)
+        .min_by_key(|k| (f(k), k.0))
+    {
+        // 2. Merge its runs minus the already expanded states with same h
+        let (segment, count) = {
+            let closed_same_h: Vec<PathBuf> = buckets
+                .iter()
+                .filter(|&(k, _)| k.1 == key.1)
+                .flat_map(|(_, b)| b.segments.iter().cloned())
+                .collect();
+
+            let bucket = buckets.get_mut(&key).unwrap();
+            buffered -= bucket.buffer.len() / ws.record_len;
+            if !bucket.buffer.is_empty() {
+                let run = ws.write_run(&mut bucket.buffer)?;
+                bucket.runs.push(run);
+            }
+
+            let mut is_goal = false;
+            let result = subtract(&mut ws, &bucket.runs, &closed_same_h, |r| {
+                is_goal = is_goal || &r[..goal_state.len()] == goal_state;
+            })?;
+
+            remove_all(&mut bucket.runs);
+            bucket.pending = 0;
+            if is_goal {
+                found = Some(key);
+            }
+
+            result
+        };
+
+        buckets.get_mut(&key).unwrap().segments.push(segment.clone());
+        closed += count;
+
+        if found.is_some() {
+            status = Status::Solved;
+            break;
+        }
+
+        // 3. Expand the new segment into the buckets of g + 1
+        let mut reader = RunReader::open(&segment, ws.record_len)?;
+
+        while let Some(record) = reader.next()? {
+            if options.is_cancelled() {
+                status = Status::Cancelled;
+                break;
+            }
+
+            let (state, parent) = decode(&record);
+            let pos = find_empty_pos(&state);
+
+            for (neighbor, neighbor_pos) in neighbors(&state, pos, problem.size) {
+                if neighbor_pos == parent {
+                    continue;
+                }
+
+                let h_result = problem.heuristic.eval(&neighbor, &problem.end, problem.size);
+                let bucket = buckets.entry((key.0 + 1, h_result)).or_default();
+
+                bucket.buffer.extend(encode(&neighbor, pos));
+                bucket.pending += 1;
+                buffered += 1;
+                opened += 1;
+            }
+
+            max_states = ::std::cmp::max(max_states, buffered);
+            expanded += 1;
+
+            if let Some(ref mut reporter) = options.reporter {
+                reporter.tick(f(&key), expanded, opened - closed, closed);
+            }
+
+            // 4. Spill every buffer once over budget
+            if buffered >= budget {
+                for bucket in buckets.values_mut() {
+                    if !bucket.buffer.is_empty() {
+                        let run = ws.write_run(&mut bucket.buffer)?;
+                        bucket.runs.push(run);
+                    }
+                }
+                buffered = 0;
+            }
+        }
+
+        if status == Status::Cancelled {
+            break;
+        }
+    }
+
+    // Unwind the path through the parent blank positions
+    let mut path = Vec::new();
+
+    if let Some((g, _)) = found {
+        let mut current = problem.end.clone();
+        let mut depth = g;
+
+        path.push(current.clone());
+        while depth > 0 {
+            let state = encode(&current, 0);
+            let h_result = problem.heuristic.eval(&current, &problem.end, problem.size);
+            let segments = match buckets.get(&(depth, h_result)) {
+                Some(b) => b.segments.clone(),
+                None => vec![],
+            };
+
+            match lookup(&ws, &segments, &state[..ws.state_len()])? {
+                Some(parent) => {
+                    let pos = find_empty_pos(&current);
+                    current.swap(pos as usize, parent as usize);
+                    path.push(current.clone());
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "external: lost a state while unwinding the path",
+                    ))
+                }
+            }
+            depth -= 1;
+        }
+    }
+
+    path.reverse();
+
+    let current_open_states = buckets.values().map(|b| b.pending).sum();
+
+    Ok(Solution {
+        problem: Cow::Borrowed(problem),
+        status,
+        path,
+        max_states,
+        opened_states: opened,
+        current_open_states,
+        closed_states: closed,
+        time: clock::since(options.clock, start_time),
+    })
+}
+
+// Breadth-first enumeration of every state reachable from `start`, returning
+// the number of states at each distance. The graph is undirected, so the
+// successors of layer d can only be in layers d - 1, d or d + 1.
+pub fn bfs(start: &Puzzle, size: Atom, config: &DiskConfig) -> io::Result<Vec<Statistic>> {
+    let mut ws = Workspace::create(config, size)?;
+    let budget = ws.budget(config.memory);
+
+    let mut layers = vec![1];
+    let mut previous: Vec<PathBuf> = vec![];
+    let mut current = ws.write_run(&mut encode(start, NO_PARENT))?;
+
+    loop {
+        let mut runs = vec![];
+        let mut buffer = vec![];
+        let mut reader = RunReader::open(&current, ws.record_len)?;
+
+        while let Some(record) = reader.next()? {
+            let (state, parent) = decode(&record);
+            let pos = find_empty_pos(&state);
+
+            for (neighbor, neighbor_pos) in neighbors(&state, pos, size) {
+                if neighbor_pos != parent {
+                    buffer.extend(encode(&neighbor, pos));
+                }
+            }
+
+            if buffer.len() / ws.record_len >= budget {
+                runs.push(ws.write_run(&mut buffer)?);
+            }
+        }
+        if !buffer.is_empty() {
+            runs.push(ws.write_run(&mut buffer)?);
+        }
+
+        let mut closed = previous.clone();
+        closed.push(current.clone());
+
+        let (next, count) = subtract(&mut ws, &runs, &closed, |_| {})?;
+        remove_all(&mut runs);
+        remove_all(&mut previous);
+
+        if count == 0 {
+            break;
+        }
+
+        layers.push(count);
+        previous = vec![current];
+        current = next;
+    }
+
+    Ok(layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cancel::CancelToken;
+    use generator;
+    use heuristics;
+    use types::Heuristic;
+
+    fn problem(start: Puzzle, end: Puzzle) -> Problem {
+        Problem {
+            start,
+            end,
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 1,
+        }
+    }
+
+    #[test]
+    fn encode_decode() {
+        let puzzle: Puzzle = vec![1, 2, 3, 8, 0, 4, 7, 6, 300];
+        let record = encode(&puzzle, 5);
+
+        assert_eq!(record.len(), 20);
+        assert_eq!(decode(&record), (puzzle, 5));
+    }
+
+    #[test]
+    fn encode_order() {
+        let a = encode(&vec![1, 2, 256], NO_PARENT);
+        let b = encode(&vec![1, 3, 0], 0);
+
+        assert!(a[..6] < b[..6]);
+    }
+
+    #[test]
+    fn workspaces_apart() {
+        let config = DiskConfig::default();
+        let a = Workspace::create(&config, 3).unwrap();
+        let b = Workspace::create(&config, 3).unwrap();
+        let dir = b.dir.clone();
+
+        assert_ne!(a.dir, b.dir);
+        drop(a);
+        assert!(dir.is_dir());
+        drop(b);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn write_run_sorted_unique() {
+        let mut ws = Workspace::create(&DiskConfig::default(), 3).unwrap();
+        let mut records = Vec::new();
+
+        for puzzle in [generator::snail(3), generator::classic(3), generator::snail(3)].iter() {
+            records.extend(encode(puzzle, 0));
+        }
+        let run = ws.write_run(&mut records).unwrap();
+        let mut reader = RunReader::open(&run, ws.record_len).unwrap();
+
+        assert!(records.is_empty());
+        assert_eq!(decode(&reader.next().unwrap().unwrap()).0, generator::classic(3));
+        assert_eq!(decode(&reader.next().unwrap().unwrap()).0, generator::snail(3));
+        assert!(reader.next().unwrap().is_none());
+        assert_eq!(ws.budget(ws.record_len + mem::size_of::<usize>()), 1);
+    }
+
+    #[test]
+    fn astar_optimal_length() {
+        let p = problem(vec![7, 1, 8, 5, 2, 6, 3, 4, 0], generator::classic(3));
+        let solution = astar(&p, &DiskConfig::default(), &mut SearchOptions::default()).unwrap();
+
+        assert_eq!(solution.path.len(), 25);
+        assert_eq!(solution.path[0], p.start);
+        assert_eq!(solution.path[solution.path.len() - 1], p.end);
+    }
+
+    #[test]
+    fn astar_small_memory() {
+        let p = problem(vec![7, 1, 8, 5, 2, 6, 3, 4, 0], generator::classic(3));
+        let config = DiskConfig {
+            memory: 4096,
+            ..DiskConfig::default()
+        };
+        let solution = astar(&p, &config, &mut SearchOptions::default()).unwrap();
+
+        assert_eq!(solution.path.len(), 25);
+        for w in solution.path.windows(2) {
+            assert_eq!(heuristics::hamming(&w[0], &w[1], 3), 1);
+        }
+    }
+
+    #[test]
+    fn astar_identity() {
+        let p = problem(generator::snail(3), generator::snail(3));
+        let solution = astar(&p, &DiskConfig::default(), &mut SearchOptions::default()).unwrap();
+
+        assert_eq!(solution.path, vec![p.end.clone()]);
+        assert_eq!(solution.status, Status::Solved);
+    }
+
+    #[test]
+    fn astar_cancel() {
+        let p = problem(vec![7, 1, 8, 5, 2, 6, 3, 4, 0], generator::classic(3));
+        let token = CancelToken::new();
+        let mut options = SearchOptions {
+            cancel: Some(token.clone()),
+            ..SearchOptions::default()
+        };
+
+        token.cancel();
+        let solution = astar(&p, &DiskConfig::default(), &mut options).unwrap();
+
+        assert_eq!(solution.status, Status::Cancelled);
+        assert!(solution.path.is_empty());
+    }
+
+    #[test]
+    fn bfs_3x3() {
+        let config = DiskConfig {
+            memory: 1024 * 1024,
+            ..DiskConfig::default()
+        };
+        let layers = bfs(&generator::classic(3), 3, &config).unwrap();
+
+        assert_eq!(layers.iter().sum::<Statistic>(), 181440);
+        assert_eq!(layers.len(), 32);
+        assert_eq!(&layers[..4], &[1, 2, 4, 8]);
+    }
+}
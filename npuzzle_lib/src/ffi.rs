@@ -0,0 +1,361 @@
+// C interface. With the header feature, build.rs generates npuzzle.h from this
+// file with cbindgen, the `///` comments becoming the documentation of the
+// header, and the tests check that include/npuzzle.h matches it.
+// Nothing here may unwind into the caller: arguments are checked up front and
+// every body runs under catch_unwind, a failure being reported as NULL or a
+// negative value. This only holds with panic = "unwind", which the release
+// profile of this crate sets. The profile of the crate being built wins, so
+// inside the root binary, built with panic = "abort", a panic still aborts.
+
+use std::os::raw::c_int;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+
+use checker;
+use game;
+use generator;
+use input_parser::puzzle_error;
+use solver::Budget;
+use solver::Search;
+use types::Atom;
+use types::Heuristic;
+use types::ParsedPuzzle;
+use types::Problem;
+use types::Puzzle;
+use types::SearchOptions;
+use types::Status;
+use types::MAX_PUZZLE_SIZE;
+
+/// Goal types for npuzzle_problem_new
+pub const NPUZZLE_GOAL_SNAIL: c_int = 0;
+pub const NPUZZLE_GOAL_CLASSIC: c_int = 1;
+
+/// Heuristics for npuzzle_problem_set_heuristic, linear conflicts by default
+pub const NPUZZLE_HEURISTIC_CONFLICTS: c_int = 0;
+pub const NPUZZLE_HEURISTIC_HAMMING: c_int = 1;
+pub const NPUZZLE_HEURISTIC_MANHATTAN: c_int = 2;
+
+/// Values of npuzzle_solution.status
+pub const NPUZZLE_SOLVED: c_int = 0;
+pub const NPUZZLE_UNSOLVABLE: c_int = 1;
+pub const NPUZZLE_OUT_OF_BUDGET: c_int = 2;
+
+#[repr(C)]
+pub struct NpuzzleSolution {
+    pub status: c_int,
+    /// 'U', 'D', 'L' or 'R' for the direction each tile slides into the
+    /// blank, the blank itself moving the opposite way. Not NUL terminated.
+    pub moves: *mut u8,
+    pub moves_len: usize,
+    pub expanded: u64,
+    pub closed_states: u64,
+    pub max_states: u64,
+}
+
+fn guard<T, F: FnOnce() -> T>(failed: T, f: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(failed)
+}
+
+unsafe fn read_puzzle(tiles: *const u16, size: usize) -> Option<Puzzle> {
+    if tiles.is_null() || size < 3 || size > MAX_PUZZLE_SIZE as usize {
+        return None;
+    }
+
+    let puzzle = ParsedPuzzle {
+        container: slice::from_raw_parts(tiles, size * size).to_vec(),
+        size: size as Atom,
+    };
+
+    match puzzle_error(&puzzle) {
+        None => Some(puzzle.container),
+        Some(_) => None,
+    }
+}
+
+fn into_raw(start: Puzzle, end: Puzzle, size: usize) -> *mut Problem {
+    Box::into_raw(Box::new(Problem {
+        start,
+        end,
+        size: size as Atom,
+        heuristic: Heuristic::Conflicts,
+        g_weight: 1,
+        h_weight: 1,
+    }))
+}
+
+/// `tiles` holds size * size values, 0 being the blank. NULL if invalid.
+///
+/// # Safety
+///
+/// `tiles` is NULL or points to size * size readable values.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_problem_new(tiles: *const u16, size: usize, goal: c_int) -> *mut Problem {
+    guard(ptr::null_mut(), || {
+        let start = match read_puzzle(tiles, size) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let end = match goal {
+            NPUZZLE_GOAL_SNAIL => generator::snail(size as Atom),
+            NPUZZLE_GOAL_CLASSIC => generator::classic(size as Atom),
+            _ => return ptr::null_mut(),
+        };
+
+        into_raw(start, end, size)
+    })
+}
+
+/// Same as npuzzle_problem_new, with the goal given tile by tile.
+///
+/// # Safety
+///
+/// `tiles` and `goal_tiles` are each NULL or point to size * size readable
+/// values.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_problem_new_with_goal(
+    tiles: *const u16,
+    goal_tiles: *const u16,
+    size: usize,
+) -> *mut Problem {
+    guard(ptr::null_mut(), || {
+        match (read_puzzle(tiles, size), read_puzzle(goal_tiles, size)) {
+            (Some(start), Some(end)) => into_raw(start, end, size),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// # Safety
+///
+/// `problem` is NULL or was returned by npuzzle_problem_new or
+/// npuzzle_problem_new_with_goal, and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_problem_free(problem: *mut Problem) {
+    if !problem.is_null() {
+        drop(Box::from_raw(problem));
+    }
+}
+
+/// 0 on success, -1 on invalid arguments
+///
+/// # Safety
+///
+/// `problem` is NULL or a live problem not used by another thread meanwhile.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_problem_set_heuristic(problem: *mut Problem, heuristic: c_int) -> c_int {
+    let heuristic = match heuristic {
+        NPUZZLE_HEURISTIC_CONFLICTS => Heuristic::Conflicts,
+        NPUZZLE_HEURISTIC_HAMMING => Heuristic::Hamming,
+        NPUZZLE_HEURISTIC_MANHATTAN => Heuristic::Manhattan,
+        _ => return -1,
+    };
+
+    match problem.as_mut() {
+        Some(p) => {
+            p.heuristic = heuristic;
+            0
+        }
+        None => -1,
+    }
+}
+
+/// 0 on success, -1 on invalid arguments
+///
+/// # Safety
+///
+/// `problem` is NULL or a live problem not used by another thread meanwhile.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_problem_set_weights(problem: *mut Problem, g_weight: u64, h_weight: u64) -> c_int {
+    match problem.as_mut() {
+        Some(p) => {
+            p.g_weight = g_weight;
+            p.h_weight = h_weight;
+            0
+        }
+        None => -1,
+    }
+}
+
+/// 1 if solvable, 0 if not, -1 on invalid arguments
+///
+/// # Safety
+///
+/// `problem` is NULL or a live problem.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_is_solvable(problem: *const Problem) -> c_int {
+    match problem.as_ref() {
+        Some(p) => guard(-1, || checker::is_solvable(&p.start, &p.end, p.size) as c_int),
+        None => -1,
+    }
+}
+
+/// Zero for either budget means no limit. Free the result with
+/// npuzzle_solution_free.
+///
+/// # Safety
+///
+/// `problem` is NULL or a live problem.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_solve(
+    problem: *const Problem,
+    max_expanded: u64,
+    timeout_ms: u64,
+) -> *mut NpuzzleSolution {
+    let problem = match problem.as_ref() {
+        Some(p) => p,
+        None => return ptr::null_mut(),
+    };
+
+    guard(ptr::null_mut(), || {
+        let mut solution = NpuzzleSolution {
+            status: NPUZZLE_UNSOLVABLE,
+            moves: ptr::null_mut(),
+            moves_len: 0,
+            expanded: 0,
+            closed_states: 0,
+            max_states: 0,
+        };
+
+        if !checker::is_solvable(&problem.start, &problem.end, problem.size) {
+            return Box::into_raw(Box::new(solution));
+        }
+
+        let budget = Budget {
+            expanded: if max_expanded > 0 { Some(max_expanded as usize) } else { None },
+            time: if timeout_ms > 0 { Some(Duration::from_millis(timeout_ms)) } else { None },
+        };
+        let mut search = Search::new(problem);
+        search.run(&mut SearchOptions::default(), &budget);
+        let result = search.solution();
+
+        solution.expanded = search.expanded() as u64;
+        solution.closed_states = result.closed_states as u64;
+        solution.max_states = result.max_states as u64;
+        solution.status = match result.status {
+            Status::Solved => NPUZZLE_SOLVED,
+            Status::Unsolvable => NPUZZLE_UNSOLVABLE,
+            Status::Cancelled | Status::OutOfBudget | Status::InProgress => NPUZZLE_OUT_OF_BUDGET,
+        };
+
+        if result.status == Status::Solved {
+            let moves = game::to_moves(&result.path, problem.size).into_bytes().into_boxed_slice();
+
+            solution.moves_len = moves.len();
+            solution.moves = Box::into_raw(moves) as *mut u8;
+        }
+
+        Box::into_raw(Box::new(solution))
+    })
+}
+
+/// # Safety
+///
+/// `solution` is NULL or was returned by npuzzle_solve, and is not used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_solution_free(solution: *mut NpuzzleSolution) {
+    if solution.is_null() {
+        return;
+    }
+
+    let solution = Box::from_raw(solution);
+    if !solution.moves.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(solution.moves, solution.moves_len)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = include_str!("../include/npuzzle.h");
+
+    #[test]
+    fn solve_classic() {
+        let tiles: [u16; 9] = [1, 2, 3, 4, 5, 6, 0, 7, 8];
+
+        unsafe {
+            let problem = npuzzle_problem_new(tiles.as_ptr(), 3, NPUZZLE_GOAL_CLASSIC);
+            assert!(!problem.is_null());
+            assert_eq!(npuzzle_problem_set_heuristic(problem, 2), 0);
+            assert_eq!(npuzzle_is_solvable(problem), 1);
+
+            let solution = npuzzle_solve(problem, 0, 0);
+            assert_eq!((*solution).status, NPUZZLE_SOLVED);
+            assert_eq!(
+                slice::from_raw_parts((*solution).moves, (*solution).moves_len),
+                b"LL"
+            );
+
+            npuzzle_solution_free(solution);
+            npuzzle_problem_free(problem);
+        }
+    }
+
+    #[test]
+    fn solve_out_of_budget() {
+        let tiles: [u16; 9] = [7, 1, 8, 5, 2, 6, 3, 4, 0];
+
+        unsafe {
+            let problem = npuzzle_problem_new(tiles.as_ptr(), 3, NPUZZLE_GOAL_CLASSIC);
+            let solution = npuzzle_solve(problem, 10, 0);
+
+            assert_eq!((*solution).status, NPUZZLE_OUT_OF_BUDGET);
+            assert_eq!((*solution).expanded, 10);
+            assert!((*solution).moves.is_null());
+
+            npuzzle_solution_free(solution);
+            npuzzle_problem_free(problem);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        let duplicated: [u16; 9] = [1, 1, 3, 4, 5, 6, 0, 7, 8];
+
+        unsafe {
+            assert!(npuzzle_problem_new(ptr::null(), 3, NPUZZLE_GOAL_SNAIL).is_null());
+            assert!(npuzzle_problem_new(duplicated.as_ptr(), 3, NPUZZLE_GOAL_SNAIL).is_null());
+            assert!(npuzzle_problem_new(duplicated.as_ptr(), 65535, NPUZZLE_GOAL_SNAIL).is_null());
+            assert_eq!(npuzzle_problem_set_heuristic(ptr::null_mut(), 0), -1);
+            assert!(npuzzle_solve(ptr::null(), 0, 0).is_null());
+            npuzzle_problem_free(ptr::null_mut());
+            npuzzle_solution_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn guard_catches_panics() {
+        assert_eq!(guard(-1, || panic!("failed")), -1);
+        assert_eq!(guard(-1, || 1), 1);
+    }
+
+    #[cfg(feature = "header")]
+    #[test]
+    fn header_is_current() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/npuzzle.h"));
+
+        assert!(
+            HEADER == generated,
+            "include/npuzzle.h is out of date, replace it with {}/npuzzle.h",
+            env!("OUT_DIR")
+        );
+    }
+
+    #[test]
+    fn header_declares_everything() {
+        let source = include_str!("ffi.rs");
+
+        for line in source.lines().filter(|l| l.starts_with("pub unsafe extern \"C\" fn ")) {
+            let name = line["pub unsafe extern \"C\" fn ".len()..].split('(').next().unwrap();
+            assert!(HEADER.contains(&format!("{}(", name)), "{} missing from header", name);
+        }
+        for line in source.lines().filter(|l| l.starts_with("pub const NPUZZLE_")) {
+            let name = line["pub const ".len()..].split(':').next().unwrap();
+            assert!(HEADER.contains(name), "{} missing from header", name);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use checker::is_solvable;
+use solver;
+use types::Atom;
+use types::Heuristic;
+use types::Problem;
+use types::Puzzle;
+use types::Statistic;
+
+use util::find_empty_pos;
+use util::format_puzzle;
+use util::xy;
+
+// Direction in which a tile slides into the blank
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+pub struct Game {
+    pub puzzle: Puzzle,
+    pub goal: Puzzle,
+    pub size: Atom,
+    pub moves: Statistic,
+}
+
+// Direction of the tile moving from `from` into the blank at `to`
+pub fn direction(from: Atom, to: Atom, size: Atom) -> Option<Direction> {
+    let (fx, fy) = xy(from, size);
+    let (tx, ty) = xy(to, size);
+
+    match (tx as i32 - fx as i32, ty as i32 - fy as i32) {
+        (0, -1) => Some(Direction::Up),
+        (0, 1) => Some(Direction::Down),
+        (-1, 0) => Some(Direction::Left),
+        (1, 0) => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+impl Direction {
+    pub fn letter(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        }
+    }
+
+    pub fn from_letter(c: char) -> Option<Direction> {
+        match c.to_ascii_uppercase() {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+// Move string of a path, one letter per sliding tile: "ULDR..."
+pub fn to_moves(path: &[Puzzle], size: Atom) -> String {
+    path.windows(2)
+        .filter_map(|w| direction(find_empty_pos(&w[1]), find_empty_pos(&w[0]), size))
+        .map(|d| d.letter())
+        .collect()
+}
+
+// Path obtained by playing a move string from `start`, None if a move is
+// invalid or impossible
+pub fn apply_moves(start: &Puzzle, size: Atom, moves: &str) -> Option<Vec<Puzzle>> {
+    let mut game = Game {
+        puzzle: start.clone(),
+        goal: start.clone(),
+        size,
+        moves: 0,
+    };
+    let mut path = vec![start.clone()];
+
+    for c in moves.chars().filter(|c| !c.is_whitespace()) {
+        if !game.play(Direction::from_letter(c)?) {
+            return None;
+        }
+        path.push(game.puzzle.clone());
+    }
+
+    Some(path)
+}
+
+// Tile that slid between two consecutive states of a solution
+pub fn moved_tile(before: &Puzzle, after: &Puzzle) -> Option<Atom> {
+    match after[find_empty_pos(before) as usize] {
+        0 => None,
+        tile => Some(tile),
+    }
+}
+
+impl Game {
+    // Refuses setups that cannot reach the goal
+    pub fn new(puzzle: Puzzle, goal: Puzzle, size: Atom) -> Option<Game> {
+        if !is_solvable(&puzzle, &goal, size) {
+            return None;
+        }
+
+        Some(Game {
+            puzzle,
+            goal,
+            size,
+            moves: 0,
+        })
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.puzzle == self.goal
+    }
+
+    // Slides the tile next to the blank in `dir`, returns false if there is none
+    pub fn play(&mut self, dir: Direction) -> bool {
+        let blank = find_empty_pos(&self.puzzle);
+        let (x, y) = xy(blank, self.size);
+
+        let tile = match dir {
+            Direction::Up if y + 1 < self.size => blank + self.size,
+            Direction::Down if y > 0 => blank - self.size,
+            Direction::Left if x + 1 < self.size => blank + 1,
+            Direction::Right if x > 0 => blank - 1,
+            _ => return false,
+        };
+
+        self.puzzle.swap(blank as usize, tile as usize);
+        self.moves += 1;
+
+        true
+    }
+
+    // Next move of an optimal solution from the current state
+    pub fn hint(&self, heuristic: Heuristic) -> Option<Direction> {
+        let problem = Problem {
+            start: self.puzzle.clone(),
+            end: self.goal.clone(),
+            size: self.size,
+            heuristic,
+            g_weight: 1,
+            h_weight: 1,
+        };
+        let solution = solver::astar(&problem);
+
+        if solution.path.len() < 2 {
+            return None;
+        }
+
+        let blank = find_empty_pos(&solution.path[0]);
+        let next_blank = find_empty_pos(&solution.path[1]);
+
+        direction(next_blank, blank, self.size)
+    }
+
+    pub fn render(&self) -> String {
+        format_puzzle(&self.puzzle, self.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use generator;
+
+    #[test]
+    fn new_unsolvable() {
+        let goal = generator::classic(3);
+        let puzzle: Puzzle = vec![2, 1, 3, 4, 5, 6, 7, 8, 0];
+
+        assert!(Game::new(puzzle, goal, 3).is_none());
+    }
+
+    #[test]
+    fn play() {
+        let goal = generator::classic(3);
+        let puzzle: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 0, 8];
+        let mut game = Game::new(puzzle, goal, 3).unwrap();
+
+        assert!(!game.play(Direction::Up));
+        assert!(game.play(Direction::Right));
+        assert!(game.play(Direction::Left));
+        assert!(game.play(Direction::Left));
+        assert!(game.is_solved());
+        assert_eq!(game.moves, 3);
+    }
+
+    #[test]
+    fn moved_tile() {
+        let a: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 0, 8];
+        let b: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
+
+        assert_eq!(super::moved_tile(&a, &b), Some(8));
+        assert_eq!(super::moved_tile(&a, &a), None);
+    }
+
+    #[test]
+    fn moves_round_trip() {
+        let start: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 0, 8];
+        let path = apply_moves(&start, 3, "RLL").unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[3], generator::classic(3));
+        assert_eq!(to_moves(&path, 3), "RLL");
+        assert!(apply_moves(&start, 3, "U").is_none());
+        assert!(apply_moves(&start, 3, "X").is_none());
+    }
+
+    #[test]
+    fn hint() {
+        let goal = generator::classic(3);
+        let puzzle: Puzzle = vec![1, 2, 3, 4, 5, 6, 7, 0, 8];
+        let game = Game::new(puzzle, goal, 3).unwrap();
+
+        assert_eq!(game.hint(Heuristic::Manhattan), Some(Direction::Left));
+    }
+}
@@ -1,6 +1,8 @@
 extern crate rand;
 
 use generator::rand::distributions::{Distribution, Uniform};
+use generator::rand::prng::XorShiftRng;
+use generator::rand::{Rng, SeedableRng};
 
 use solver::neighbors;
 use util::find_empty_pos;
@@ -9,7 +11,7 @@ use types::Atom;
 use types::ParsedPuzzle;
 use types::Puzzle;
 
-pub fn generate_valid_puzzle(puzzle: &ParsedPuzzle, iterations: u64) -> ParsedPuzzle {
+fn random_walk<R: Rng>(puzzle: &ParsedPuzzle, iterations: u64, rng: &mut R) -> ParsedPuzzle {
     let mut state = ParsedPuzzle {
         container: puzzle.container.clone(),
         size: puzzle.size,
@@ -17,27 +19,40 @@ pub fn generate_valid_puzzle(puzzle: &ParsedPuzzle, iterations: u64) -> ParsedPu
 
     let mut last_pos = find_empty_pos(&state.container);
     let between = Uniform::from(0..4);
-    let mut rng = rand::thread_rng();
 
     for _i in 0..iterations {
-        let neighbors = neighbors(&state.container, last_pos, state.size);
-
-        let mut swap_n = between.sample(&mut rng) % neighbors.len();
-
-        let mut n = 0;
-        for neighbor in neighbors {
-            if n == swap_n {
-                state.container = neighbor.0;
-                last_pos = neighbor.1;
-                break;
-            }
-            n += 1
-        }
+        // sorted, so that a seeded walk does not depend on the hash order
+        let mut neighbors: Vec<(Puzzle, Atom)> =
+            neighbors(&state.container, last_pos, state.size).into_iter().collect();
+        neighbors.sort_by_key(|n| n.1);
+
+        let swap_n = between.sample(rng) % neighbors.len();
+        let neighbor = neighbors.swap_remove(swap_n);
+
+        state.container = neighbor.0;
+        last_pos = neighbor.1;
     }
 
     state
 }
 
+#[cfg(feature = "native")]
+pub fn generate_valid_puzzle(puzzle: &ParsedPuzzle, iterations: u64) -> ParsedPuzzle {
+    random_walk(puzzle, iterations, &mut rand::thread_rng())
+}
+
+// Same as generate_valid_puzzle, but always gives the same puzzle for a seed
+pub fn generate_seeded_puzzle(puzzle: &ParsedPuzzle, iterations: u64, seed: u64) -> ParsedPuzzle {
+    let mut bytes = [0u8; 16];
+
+    for i in 0..8 {
+        bytes[i] = (seed >> (i * 8)) as u8;
+        bytes[i + 8] = !bytes[i];
+    }
+
+    random_walk(puzzle, iterations, &mut XorShiftRng::from_seed(bytes))
+}
+
 pub fn classic(size: Atom) -> Puzzle {
     let mut solution: Puzzle = (1..size * size + 1).collect();
     let ssize: usize = size as usize * size as usize;
@@ -80,6 +95,22 @@ pub fn snail(size: Atom) -> Puzzle {
 
 #[cfg(test)]
 mod tests {
+    use types::ParsedPuzzle;
+
+    #[test]
+    fn generate_seeded_puzzle() {
+        let goal = ParsedPuzzle {
+            container: super::snail(4),
+            size: 4,
+        };
+        let a = super::generate_seeded_puzzle(&goal, 100, 42);
+        let b = super::generate_seeded_puzzle(&goal, 100, 42);
+        let c = super::generate_seeded_puzzle(&goal, 100, 43);
+
+        assert_eq!(a.container, b.container);
+        assert_ne!(a.container, c.container);
+    }
+
     #[test]
     fn classic_3x3() {
         let v = super::classic(3);
@@ -1,9 +1,62 @@
 use types::Atom;
 use types::AtomPair;
+use types::Heuristic;
 use types::Puzzle;
 use types::Result;
 use util::xy;
 
+pub static HEURISTICS: [(&str, Heuristic); 3] = [
+    ("conflicts", Heuristic::Conflicts),
+    ("hamming", Heuristic::Hamming),
+    ("manhattan", Heuristic::Manhattan),
+];
+
+impl Heuristic {
+    pub fn eval(self, a: &Puzzle, b: &Puzzle, size: Atom) -> Result {
+        match self {
+            Heuristic::Conflicts => linear_conflicts(a, b, size),
+            Heuristic::Hamming => hamming(a, b, size),
+            Heuristic::Manhattan => manhattan(a, b, size),
+        }
+    }
+}
+
+pub fn from_name(name: &str) -> Option<Heuristic> {
+    HEURISTICS.iter().find(|h| h.0 == name).map(|h| h.1)
+}
+
+pub fn name_of(heuristic: Heuristic) -> Option<&'static str> {
+    HEURISTICS
+        .iter()
+        .find(|h| h.1 == heuristic)
+        .map(|h| h.0)
+}
+
+// Heuristics are serialized by name, through serde's `with` attribute
+#[cfg(feature = "serde")]
+pub mod by_name {
+    use serde::de::Error as DeError;
+    use serde::ser::Error as SerError;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    use types::Heuristic;
+
+    pub fn serialize<S: Serializer>(heuristic: &Heuristic, serializer: S) -> Result<S::Ok, S::Error> {
+        match super::name_of(*heuristic) {
+            Some(name) => serializer.serialize_str(name),
+            None => Err(S::Error::custom("heuristic has no name")),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Heuristic, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        super::from_name(&name).ok_or_else(|| D::Error::custom(format!("unknown heuristic {}", name)))
+    }
+}
+
 #[inline]
 fn dist(a: AtomPair, b: AtomPair) -> Result {
     let x: i32 = a.0 as i32 - b.0 as i32;
@@ -12,126 +65,83 @@ fn dist(a: AtomPair, b: AtomPair) -> Result {
     (x.abs() + y.abs()) as Result
 }
 
-pub fn hamming(a: &Puzzle, b: &Puzzle, _size: Atom) -> Result {
-    let mut total: Result = 0;
+// Where every tile sits in the goal, indexed by tile
+fn goal_positions(goal: &Puzzle) -> Vec<Atom> {
+    let mut positions = vec![0; goal.len()];
 
-    if a == b {
-        return 0;
+    for (i, &tile) in goal.iter().enumerate() {
+        positions[tile as usize] = i as Atom;
     }
 
-    for i in 0..a.len() {
-        let a_val = a[i];
-        let b_val = b[i];
+    positions
+}
 
-        if a_val != b_val {
-            total += 1;
-        }
-    }
+// The blank is not a tile: counting it would overestimate the moves left,
+// and A* would then miss the shortest paths
 
-    total
+pub fn hamming(a: &Puzzle, b: &Puzzle, _size: Atom) -> Result {
+    a.iter()
+        .zip(b.iter())
+        .filter(|&(&a_val, &b_val)| a_val != 0 && a_val != b_val)
+        .count() as Result
 }
 
 pub fn manhattan(a: &Puzzle, b: &Puzzle, size: Atom) -> Result {
+    let goal = goal_positions(b);
     let mut total: Result = 0;
 
-    if a == b {
-        return 0;
+    for (i, &tile) in a.iter().enumerate() {
+        if tile != 0 {
+            total += dist(xy(i as Atom, size), xy(goal[tile as usize], size));
+        }
     }
 
-    for i in 0..a.len() {
-        let a_val = a[i];
-        let a_pos = xy(i as Atom, size);
+    total
+}
 
-        for j in 0..b.len() {
-            let b_val = b[j];
+// Tiles of a line already in their goal line, given as their goal position
+// along it. Those out of the longest increasing run have to leave the line
+// for the others to pass each other, which costs two more moves each.
+fn line_penalty(line: &[Atom]) -> Result {
+    let mut longest = vec![1; line.len()];
 
-            if a_val == b_val {
-                let b_pos = xy(j as Atom, size);
-                total += dist(a_pos, b_pos);
+    for i in 0..line.len() {
+        for j in 0..i {
+            if line[j] < line[i] && longest[j] + 1 > longest[i] {
+                longest[i] = longest[j] + 1;
             }
         }
     }
 
-    total
+    2 * (line.len() - longest.iter().cloned().max().unwrap_or(0)) as Result
 }
 
-// I know it's horrible, but I can't think of a better way right now
-// Also, it works so that's good enough(tm)
-
 pub fn linear_conflicts(a: &Puzzle, b: &Puzzle, size: Atom) -> Result {
+    let goal = goal_positions(b);
     let mut penalty = 0;
 
-    for row in 0..size {
-        for x in 0..size - 1 {
-            for x_2 in (x + 1)..size {
-                let pos = x + row * size;
-                let pos_2 = x_2 + row * size;
-
-                let val = a[pos as usize];
-                let val_2 = a[pos_2 as usize];
-
-                let mut goal = (0, 0);
-                let mut goal_2 = (0, 0);
-                let mut changed = 0;
-
-                for i in 0..b.len() {
-                    if b[i] == val {
-                        goal = xy(i as Atom, size);
-                        changed += 1;
-                    }
-
-                    if b[i] == val_2 {
-                        goal_2 = xy(i as Atom, size);
-                        changed += 1;
-                    }
-                }
+    for line in 0..size {
+        let mut row = Vec::new();
+        let mut col = Vec::new();
 
-                debug_assert!(changed == 2);
+        for k in 0..size {
+            let in_row = a[(k + line * size) as usize];
+            let in_col = a[(line + k * size) as usize];
 
-                if goal.1 == row && goal_2.1 == row {
-                    if goal.0 > goal_2.0 {
-                        penalty += 1;
-                    }
+            if in_row != 0 {
+                let (x, y) = xy(goal[in_row as usize], size);
+                if y == line {
+                    row.push(x);
                 }
             }
-        }
-    }
-
-    for col in 0..size {
-        for y in 0..size - 1 {
-            for y_2 in (y + 1)..size {
-                let pos = col + y * size;
-                let pos_2 = col + y_2 * size;
-
-                let val = a[pos as usize];
-                let val_2 = a[pos_2 as usize];
-
-                let mut goal = (0, 0);
-                let mut goal_2 = (0, 0);
-
-                let mut changed = 0;
-
-                for i in 0..b.len() {
-                    if b[i] == val {
-                        goal = xy(i as Atom, size);
-                        changed += 1;
-                    }
-
-                    if b[i] == val_2 {
-                        goal_2 = xy(i as Atom, size);
-                        changed += 1;
-                    }
-                }
-
-                debug_assert!(changed == 2);
-
-                if goal.0 == col && goal_2.0 == col {
-                    if goal.1 > goal_2.1 {
-                        penalty += 1;
-                    }
+            if in_col != 0 {
+                let (x, y) = xy(goal[in_col as usize], size);
+                if x == line {
+                    col.push(y);
                 }
             }
         }
+        penalty += line_penalty(&row) + line_penalty(&col);
     }
 
     manhattan(a, b, size) + penalty
@@ -141,6 +151,13 @@ pub fn linear_conflicts(a: &Puzzle, b: &Puzzle, size: Atom) -> Result {
 mod tests {
     use types::Atom;
 
+    #[test]
+    fn from_name() {
+        assert!(super::from_name("manhattan").is_some());
+        assert!(super::from_name("euclid").is_none());
+        assert_eq!(super::name_of(super::from_name("hamming").unwrap()), Some("hamming"));
+    }
+
     #[test]
     fn dist() {
         assert_eq!(super::dist((3, 3), (5, 5)), 4);
@@ -153,7 +170,7 @@ mod tests {
         let a: Vec<Atom> = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
         let b: Vec<Atom> = vec![8, 3, 1, 4, 7, 2, 5, 0, 6];
 
-        let expected = 8;
+        let expected = 7;
 
         assert_eq!(super::hamming(&a, &b, 3), expected);
         assert_eq!(super::hamming(&b, &a, 3), expected);
@@ -175,7 +192,7 @@ mod tests {
         let a: Vec<Atom> = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
         let b: Vec<Atom> = vec![8, 3, 1, 4, 7, 2, 5, 0, 6];
 
-        let expected = 14;
+        let expected = 13;
 
         assert_eq!(super::manhattan(&a, &b, 3), expected);
         assert_eq!(super::manhattan(&b, &a, 3), expected);
@@ -208,7 +225,7 @@ mod tests {
         let a: Vec<Atom> = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
         let b: Vec<Atom> = vec![3, 2, 1, 4, 5, 6, 7, 8, 0];
 
-        let expected = 7;
+        let expected = 8;
 
         assert_eq!(super::linear_conflicts(&a, &b, 3), expected);
         assert_eq!(super::linear_conflicts(&b, &a, 3), expected);
@@ -219,7 +236,7 @@ mod tests {
         let a: Vec<Atom> = vec![1, 2, 3, 4, 5, 6, 7, 8, 0];
         let b: Vec<Atom> = vec![7, 2, 3, 4, 5, 6, 1, 8, 0];
 
-        let expected = 7;
+        let expected = 8;
 
         assert_eq!(super::linear_conflicts(&a, &b, 3), expected);
         assert_eq!(super::linear_conflicts(&b, &a, 3), expected);
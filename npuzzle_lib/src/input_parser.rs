@@ -1,11 +1,25 @@
+#[cfg(feature = "native")]
+use diagnostics;
 use types::Atom;
+use types::LabeledPuzzle;
+use types::MAX_PUZZLE_SIZE;
 use types::ParsedPuzzle;
 use types::Puzzle;
 
+#[cfg(feature = "native")]
 use std::fs;
+#[cfg(feature = "native")]
 use std::io;
+#[cfg(feature = "native")]
+use std::io::Read;
+#[cfg(feature = "native")]
 use std::process;
 
+#[cfg(feature = "serde")]
+use serde_json;
+#[cfg(feature = "serde")]
+use serde_json::Value;
+
 //Possible errors in parse_puzzle_size and parse_puzzle
 pub static ERR_NUMBER_PER_LINE: &str = "Numbers per lines have to be equal to size";
 pub static ERR_NUMBER_INVALID: &str = "Invalid number";
@@ -13,6 +27,7 @@ pub static ERR_SIZE_INVALID: &str = "Invalid size";
 pub static ERR_SIZE_SYNTAX: &str = "Size declaration syntax invalid";
 pub static ERR_IO_STDIN: &str = "IO Error : Failed to read stdin. Exiting";
 pub static ERR_OPEN_FILE: &str = "Failed to open file. Exiting";
+pub static ERR_READ_FILE: &str = "Failed to read file. Skipping";
 
 //Possible errors in is_puzzle_correct
 pub static ERR_NUMBER_OF_LINES: &str = "Invalid number of lines in n-puzzle";
@@ -20,42 +35,67 @@ pub static ERR_INVALID_ELMT: &str = "The following number is superior to the max
 pub static ERR_DUPLICATED_VALUE: &str = "The following number is duplicated";
 pub static ERR_SIZE: &str = "Size must be superior or equal to 3";
 
-fn is_puzzle_correct(puzzle: &ParsedPuzzle, filename: &String) -> bool {
-    let max_number: Atom = puzzle.size * puzzle.size;
+//Possible errors in the other formats
+pub static ERR_JSON_SYNTAX: &str = "Invalid JSON";
+pub static ERR_JSON_FIELDS: &str = "JSON puzzle must be an object with a size and an array of tiles";
+pub static ERR_JSON_FEATURE: &str = "JSON puzzles need npuzzle_lib built with the serde feature";
+pub static ERR_SINGLE_LINE: &str = "Single line puzzle must be written as size:tile,tile,...";
+pub static ERR_UNKNOWN_FORMAT: &str = "Unknown format";
 
+//Possible errors in labeled files
+pub static ERR_START_MISSING: &str = "start section is missing";
+pub static ERR_SECTION_TWICE: &str = "Section given twice";
+pub static ERR_OUTSIDE_SECTION: &str = "Puzzle given outside of a start or goal section";
+pub static ERR_SIZES_DIFFER: &str = "start and goal sizes differ";
+pub static ERR_LENGTH_INVALID: &str = "Invalid expected length";
+
+// Same checks as the 42 format, without the file name
+pub fn puzzle_error(puzzle: &ParsedPuzzle) -> Option<String> {
     if puzzle.size < 3 {
-        println!("File : {} : {}", filename, ERR_SIZE);
-        return false;
+        return Some(ERR_SIZE.to_string());
+    }
+    //the number of tiles would not fit in an Atom
+    if puzzle.size > MAX_PUZZLE_SIZE {
+        return Some(ERR_SIZE_INVALID.to_string());
     }
+    let max_number: Atom = puzzle.size * puzzle.size;
+
     if puzzle.container.len() != max_number as usize {
-        println!("File : {} : {}", filename, ERR_NUMBER_OF_LINES);
-        return false;
+        return Some(ERR_NUMBER_OF_LINES.to_string());
     }
     for i in 0..max_number as usize {
         if puzzle.container[i] > max_number - 1 {
-            println!(
-                "File : {} : {} {} at puzzle index {} : {}",
-                filename,
+            return Some(format!(
+                "{} {} at puzzle index {} : {}",
                 ERR_INVALID_ELMT,
                 max_number - 1,
                 i,
                 puzzle.container[i]
-            );
-            return false;
+            ));
         }
     }
     for i in 0..max_number as usize {
         for j in (i + 1)..max_number as usize {
             if puzzle.container[i] == puzzle.container[j] {
-                println!(
-                    "File : {} : {} : {} at puzzle index {}",
-                    filename, ERR_DUPLICATED_VALUE, i, puzzle.container[i]
-                );
-                return false;
+                return Some(format!(
+                    "{} : {} at puzzle index {}",
+                    ERR_DUPLICATED_VALUE, puzzle.container[j], j
+                ));
             }
         }
     }
-    true
+    None
+}
+
+#[cfg(feature = "native")]
+fn is_puzzle_correct(puzzle: &ParsedPuzzle, filename: &String) -> bool {
+    match puzzle_error(puzzle) {
+        None => true,
+        Some(e) => {
+            println!("File : {} : {}", filename, e);
+            false
+        }
+    }
 }
 
 fn parse_puzzle_size(size: &mut Atom, line: &Vec<String>) -> Option<Vec<&'static str>> {
@@ -65,6 +105,8 @@ fn parse_puzzle_size(size: &mut Atom, line: &Vec<String>) -> Option<Vec<&'static
     for word in line {
         match (word.parse::<Atom>(), had_parse_size) {
             (Ok(0...2), false) => vec_err.push(&ERR_SIZE_INVALID),
+            //a bigger size would have room reserved for its tiles right away
+            (Ok(val), false) if val > MAX_PUZZLE_SIZE => vec_err.push(&ERR_SIZE_INVALID),
             (Ok(val), false) => {
                 *size = val;
                 had_parse_size = true;
@@ -122,31 +164,62 @@ fn parse_line(puzzle: &mut ParsedPuzzle, line: &str) -> Option<Vec<&'static str>
     };
 }
 
-fn generate_puzzle(content: &String, filename: &String) -> Option<ParsedPuzzle> {
-    let mut had_error = false;
+//Parses the 42 format, giving every error along with its line number. Once
+//the size is known to be wrong, the following lines are not looked at.
+fn parse_content(content: &str) -> (ParsedPuzzle, Vec<(u64, &'static str)>) {
     let mut puzzle = ParsedPuzzle {
         container: Vec::new(),
         size: 0,
     };
-    let mut splited_file = content.lines();
-    let mut line_nb: u64 = 1;
-
-    while let Some(line) = splited_file.next() {
-        match parse_line(&mut puzzle, &line) {
-            None => {}
-            Some(v_err) => {
-                had_error = true;
-                for e in v_err {
-                    println!("File : {} : at line {} : {}", filename, line_nb, e);
-                    if puzzle.size == 0 {
-                        return None;
-                    }
-                }
+    let mut errors = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        if let Some(v_err) = parse_line(&mut puzzle, line) {
+            if puzzle.size == 0 {
+                errors.push((i as u64 + 1, v_err[0]));
+                break;
+            }
+            for e in v_err {
+                errors.push((i as u64 + 1, e));
             }
         }
-        line_nb = line_nb + 1;
     }
-    if had_error {
+
+    (puzzle, errors)
+}
+
+//Same as parse, from a string and without printing anything
+pub fn parse_str(content: &str) -> ::std::result::Result<ParsedPuzzle, String> {
+    let (puzzle, errors) = parse_content(content);
+
+    if let Some((line_nb, e)) = errors.first() {
+        return Err(format!("at line {} : {}", line_nb, e));
+    }
+    match puzzle_error(&puzzle) {
+        None => Ok(puzzle),
+        Some(e) => Err(e),
+    }
+}
+
+//Prints every problem of the content at once, each with the line it is on
+#[cfg(feature = "native")]
+fn generate_puzzle(content: &String, filename: &String) -> Option<ParsedPuzzle> {
+    let diagnostics = diagnostics::check(content);
+
+    for d in &diagnostics {
+        println!("{}", diagnostics::render(d, content, filename));
+    }
+    if !diagnostics.is_empty() {
+        return None;
+    }
+
+    // the diagnostics should cover every error of the parser, one that slips
+    // through is still reported rather than failing silently
+    let (puzzle, errors) = parse_content(content);
+    for (line_nb, e) in &errors {
+        println!("File : {} : at line {} : {}", filename, line_nb, e);
+    }
+    if !errors.is_empty() {
         return None;
     }
     match is_puzzle_correct(&puzzle, filename) {
@@ -155,65 +228,509 @@ fn generate_puzzle(content: &String, filename: &String) -> Option<ParsedPuzzle>
     }
 }
 
-fn generate_puzzle_from_stdin() -> Option<ParsedPuzzle> {
-    let mut had_error = false;
-    let mut puzzle = ParsedPuzzle {
-        container: Vec::new(),
-        size: 0,
-    };
-    let mut line_nb: u64 = 1;
-
-    loop {
-        let mut buff = String::new();
-        match io::stdin().read_line(&mut buff) {
-            Ok(n) => {
-                //check stdin exit pattern
-                buff = buff.trim().to_string();
-                if n == 0 {
-                    break;
+#[cfg(feature = "native")]
+struct BatchEntry {
+    header: Option<String>,
+    first_line: usize,
+    puzzle: ParsedPuzzle,
+    had_error: bool,
+}
+
+#[cfg(feature = "native")]
+impl BatchEntry {
+    fn new(header: Option<String>) -> BatchEntry {
+        BatchEntry {
+            header,
+            first_line: 0,
+            puzzle: ParsedPuzzle {
+                container: Vec::new(),
+                size: 0,
+            },
+            had_error: false,
+        }
+    }
+
+    fn had_content(&self) -> bool {
+        self.first_line != 0
+    }
+
+    fn is_complete(&self) -> bool {
+        self.puzzle.size > 0
+            && self.puzzle.container.len() == self.puzzle.size as usize * self.puzzle.size as usize
+    }
+
+    fn close(self, filename: &str, batch: &mut Vec<(String, Option<ParsedPuzzle>)>) {
+        if !self.had_content() {
+            return;
+        }
+
+        let name = match self.header {
+            Some(h) => h,
+            None => format!("{}:{}", filename, self.first_line),
+        };
+        if self.had_error || !is_puzzle_correct(&self.puzzle, &name) {
+            batch.push((name, None));
+        } else {
+            batch.push((name, Some(self.puzzle)));
+        }
+    }
+}
+
+//Splits content holding several puzzles, separated by blank lines or by
+//"## name" header lines. A puzzle with an error is skipped until the next
+//separator so that the following ones are still parsed.
+#[cfg(feature = "native")]
+pub fn generate_batch(content: &str, filename: &str) -> Vec<(String, Option<ParsedPuzzle>)> {
+    let mut batch = Vec::new();
+    let mut entry = BatchEntry::new(None);
+    let mut after_blank = false;
+
+    for (i, line) in content.lines().enumerate() {
+        let line_nb = i + 1;
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("##") {
+            entry.close(filename, &mut batch);
+            entry = BatchEntry::new(Some(trimmed.trim_start_matches('#').trim().to_string()));
+            after_blank = false;
+            continue;
+        }
+        if trimmed.is_empty() {
+            after_blank = entry.had_content();
+            if entry.had_error {
+                entry.close(filename, &mut batch);
+                entry = BatchEntry::new(None);
+            }
+            continue;
+        }
+        if trimmed.starts_with('#') || entry.had_error {
+            continue;
+        }
+
+        //a line that does not fit after a blank line starts a new puzzle
+        if after_blank {
+            let mut attempt = ParsedPuzzle {
+                container: entry.puzzle.container.clone(),
+                size: entry.puzzle.size,
+            };
+            if parse_line(&mut attempt, trimmed).is_some() {
+                entry.close(filename, &mut batch);
+                entry = BatchEntry::new(None);
+            }
+            after_blank = false;
+        }
+
+        if !entry.had_content() {
+            entry.first_line = line_nb;
+        }
+        if let Some(v_err) = parse_line(&mut entry.puzzle, trimmed) {
+            entry.had_error = true;
+            for e in v_err {
+                println!("File : {} : at line {} : {}", filename, line_nb, e);
+            }
+        }
+
+        if entry.is_complete() {
+            entry.close(filename, &mut batch);
+            entry = BatchEntry::new(None);
+        }
+    }
+    entry.close(filename, &mut batch);
+
+    batch
+}
+
+//Parses every puzzle of a file, or of every file in a directory
+#[cfg(feature = "native")]
+pub fn parse_batch(path: &String) -> Vec<(String, Option<ParsedPuzzle>)> {
+    let mut files: Vec<String> = Vec::new();
+
+    match fs::read_dir(path) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                if entry.path().is_file() {
+                    files.push(entry.path().to_string_lossy().into_owned());
+                }
+            }
+            files.sort();
+        }
+        Err(_) => files.push(path.clone()),
+    }
+
+    let mut batch = Vec::new();
+    for filename in files {
+        match fs::read_to_string(&filename) {
+            Ok(s) => {
+                let mut puzzles = generate_batch(&s, &filename);
+                //a file holding a single puzzle keeps the name of the file
+                if puzzles.len() == 1 && puzzles[0].0.starts_with(&filename) {
+                    puzzles[0].0 = filename.clone();
                 }
-                //parsing
-                match parse_line(&mut puzzle, &buff) {
-                    None => line_nb = line_nb + 1,
-                    Some(v_err) => {
-                        had_error = true;
-                        for e in v_err {
-                            println!(
-                                "File : {} : at line {} : {}",
-                                "stdin".to_string(),
-                                line_nb,
-                                e
-                            );
-                        }
-                    }
+                if puzzles.is_empty() {
+                    println!("File : {} : {}", filename, ERR_NUMBER_OF_LINES);
+                    puzzles.push((filename.clone(), None));
                 }
+                batch.append(&mut puzzles);
             }
             Err(_) => {
-                println!("{}", ERR_IO_STDIN);
-                process::exit(1);
+                println!("File : {} : {}", filename, ERR_READ_FILE);
+                batch.push((filename, None));
             }
         }
     }
-    if had_error {
-        return None;
+
+    batch
+}
+
+// One puzzle on a single line, the size followed by the tiles. Lines holding
+// nothing but a comment give None.
+pub fn parse_compact(line: &str) -> ::std::result::Result<Option<ParsedPuzzle>, String> {
+    let content = line.split('#').next().unwrap_or("");
+    let mut words = content.split_whitespace();
+
+    let size = match words.next() {
+        None => return Ok(None),
+        Some(word) => match word.parse::<Atom>() {
+            Ok(val) if val < 3 => return Err(ERR_SIZE.to_string()),
+            Ok(val) if val > MAX_PUZZLE_SIZE => return Err(ERR_SIZE_INVALID.to_string()),
+            Ok(val) => val,
+            Err(_) => return Err(ERR_SIZE_INVALID.to_string()),
+        },
+    };
+
+    let mut container = Vec::with_capacity(size as usize * size as usize);
+    for word in words {
+        match word.parse::<Atom>() {
+            Ok(val) => container.push(val),
+            Err(_) => return Err(format!("{} : {}", ERR_NUMBER_INVALID, word)),
+        }
     }
-    match is_puzzle_correct(&puzzle, &"stdin".to_string()) {
-        true => Some(puzzle),
-        false => None,
+
+    let puzzle = ParsedPuzzle { container, size };
+    match puzzle_error(&puzzle) {
+        None => Ok(Some(puzzle)),
+        Some(e) => Err(e),
     }
 }
 
-pub fn parse(file: Option<&String>) -> Option<ParsedPuzzle> {
-    match file {
-        None => generate_puzzle_from_stdin(),
-        Some(filename) => match fs::read_to_string(filename) {
-            Ok(s) => generate_puzzle(&s, &filename),
-            Err(_) => {
-                println!("File : {} : {}", filename, ERR_OPEN_FILE);
-                process::exit(1);
+// Reads a whole puzzle from its content, checked like the 42 format
+pub type Parser = fn(&str) -> ::std::result::Result<ParsedPuzzle, String>;
+
+pub static FORMATS: [(&str, Parser); 4] = [
+    ("text", parse_str),
+    ("json", parse_json),
+    ("line", parse_single_line),
+    ("csv", parse_csv),
+];
+
+pub fn format_from_name(name: &str) -> Option<Parser> {
+    FORMATS.iter().find(|f| f.0 == name).map(|f| f.1)
+}
+
+// Lines without their comment, along with their number, blank ones skipped
+fn content_lines(content: &str) -> Vec<(usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.split('#').next().unwrap_or("").trim()))
+        .filter(|l| !l.1.is_empty())
+        .collect()
+}
+
+// Guesses the format from the first character of a JSON object, or from the
+// separators of the first line that is not a comment
+pub fn detect_format(content: &str) -> &'static str {
+    if content.trim_start().starts_with('{') {
+        return "json";
+    }
+
+    match content_lines(content).first() {
+        Some(l) if l.1.contains(':') => "line",
+        Some(l) if l.1.contains(',') => "csv",
+        _ => "text",
+    }
+}
+
+// Same as parse_str, in whichever format the content is written
+pub fn parse_any(content: &str) -> ::std::result::Result<ParsedPuzzle, String> {
+    match format_from_name(detect_format(content)) {
+        Some(parser) => parser(content),
+        None => Err(ERR_UNKNOWN_FORMAT.to_string()),
+    }
+}
+
+fn parse_size(word: &str) -> ::std::result::Result<Atom, String> {
+    match word.trim().parse::<Atom>() {
+        Ok(val) if val < 3 => Err(ERR_SIZE.to_string()),
+        Ok(val) if val > MAX_PUZZLE_SIZE => Err(ERR_SIZE_INVALID.to_string()),
+        Ok(val) => Ok(val),
+        Err(_) => Err(ERR_SIZE_INVALID.to_string()),
+    }
+}
+
+fn parse_tile(word: &str) -> ::std::result::Result<Atom, String> {
+    word.trim()
+        .parse::<Atom>()
+        .map_err(|_| format!("{} : {}", ERR_NUMBER_INVALID, word.trim()))
+}
+
+fn checked(puzzle: ParsedPuzzle) -> ::std::result::Result<ParsedPuzzle, String> {
+    match puzzle_error(&puzzle) {
+        None => Ok(puzzle),
+        Some(e) => Err(e),
+    }
+}
+
+// `3:1,2,3,8,0,4,7,6,5`, the size then the tiles on a single line
+pub fn parse_single_line(content: &str) -> ::std::result::Result<ParsedPuzzle, String> {
+    let lines = content_lines(content);
+
+    if lines.len() != 1 {
+        return Err(ERR_SINGLE_LINE.to_string());
+    }
+
+    let (line_nb, line) = lines[0];
+    let mut parts = line.splitn(2, ':');
+    let size = parse_size(parts.next().unwrap_or(""))
+        .map_err(|e| format!("at line {} : {}", line_nb, e))?;
+    let tiles = match parts.next() {
+        Some(t) => t,
+        None => return Err(ERR_SINGLE_LINE.to_string()),
+    };
+
+    let mut container = Vec::with_capacity(size as usize * size as usize);
+    for word in tiles.split(',') {
+        container.push(parse_tile(word).map_err(|e| format!("at line {} : {}", line_nb, e))?);
+    }
+
+    checked(ParsedPuzzle { container, size })
+}
+
+// One row per line, tiles separated by commas. The size is the number of rows.
+pub fn parse_csv(content: &str) -> ::std::result::Result<ParsedPuzzle, String> {
+    let lines = content_lines(content);
+    let size = lines.len();
+
+    if size > MAX_PUZZLE_SIZE as usize {
+        return Err(ERR_SIZE_INVALID.to_string());
+    }
+
+    let mut container = Vec::with_capacity(size * size);
+    for (line_nb, line) in lines {
+        let words: Vec<&str> = line.split(',').collect();
+
+        if words.len() != size {
+            return Err(format!("at line {} : {}", line_nb, ERR_NUMBER_PER_LINE));
+        }
+        for word in words {
+            container.push(parse_tile(word).map_err(|e| format!("at line {} : {}", line_nb, e))?);
+        }
+    }
+
+    checked(ParsedPuzzle {
+        container,
+        size: size as Atom,
+    })
+}
+
+#[cfg(feature = "serde")]
+fn json_tile(value: &Value) -> ::std::result::Result<Atom, String> {
+    match value {
+        Value::Number(n) => parse_tile(&n.to_string()),
+        _ => Err(ERR_JSON_FIELDS.to_string()),
+    }
+}
+
+// `{"size": 3, "tiles": [...]}`, tiles being flat or given as rows
+#[cfg(feature = "serde")]
+pub fn parse_json(content: &str) -> ::std::result::Result<ParsedPuzzle, String> {
+    let value: Value = serde_json::from_str(content).map_err(|e| format!("{} : {}", ERR_JSON_SYNTAX, e))?;
+
+    let size = match value.get("size") {
+        Some(Value::Number(n)) => parse_size(&n.to_string())?,
+        _ => return Err(ERR_JSON_FIELDS.to_string()),
+    };
+    let tiles = match value.get("tiles") {
+        Some(Value::Array(tiles)) => tiles,
+        _ => return Err(ERR_JSON_FIELDS.to_string()),
+    };
+
+    let mut container = Vec::with_capacity(size as usize * size as usize);
+    for tile in tiles {
+        match tile {
+            Value::Array(row) if row.len() != size as usize => return Err(ERR_NUMBER_PER_LINE.to_string()),
+            Value::Array(row) => {
+                for t in row {
+                    container.push(json_tile(t)?);
+                }
             }
-        },
+            t => container.push(json_tile(t)?),
+        }
     }
+
+    checked(ParsedPuzzle { container, size })
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn parse_json(_content: &str) -> ::std::result::Result<ParsedPuzzle, String> {
+    Err(ERR_JSON_FEATURE.to_string())
+}
+
+// `key: value` where the key is made of words, such as `start:` or `length: 24`
+fn label(line: &str) -> Option<(String, &str)> {
+    let colon = line.find(':')?;
+    let key = line[..colon].trim();
+
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic() || c == ' ' || c == '_' || c == '-') {
+        return None;
+    }
+
+    Some((key.to_ascii_lowercase(), line[colon + 1..].trim()))
+}
+
+fn is_section(key: &str) -> bool {
+    key == "start" || key == "goal"
+}
+
+// Whether the content holds `start:` or `goal:` sections
+pub fn is_labeled(content: &str) -> bool {
+    content_lines(content)
+        .iter()
+        .any(|l| label(l.1).is_some_and(|(key, _)| is_section(&key)))
+}
+
+// A `start:` section, an optional `goal:` section and metadata lines, in any
+// order. Sections hold a puzzle in any of the FORMATS, which may begin on the
+// line of their label.
+pub fn parse_labeled(content: &str) -> ::std::result::Result<LabeledPuzzle, String> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    let mut metadata = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let uncommented = line.split('#').next().unwrap_or("");
+
+        match label(uncommented) {
+            Some((key, rest)) if is_section(&key) => {
+                if sections.iter().any(|s| s.0 == key) {
+                    return Err(format!("at line {} : {} : {}", i + 1, ERR_SECTION_TWICE, key));
+                }
+                //blank lines keep line numbers in errors those of the file
+                sections.push((key, format!("{}{}\n", "\n".repeat(i), rest)));
+            }
+            Some((key, value)) => metadata.push((key, value.to_string())),
+            None => match sections.last_mut() {
+                Some(section) => {
+                    section.1.push_str(line);
+                    section.1.push('\n');
+                }
+                None if uncommented.trim().is_empty() => {}
+                None => return Err(format!("at line {} : {}", i + 1, ERR_OUTSIDE_SECTION)),
+            },
+        }
+    }
+
+    let mut start = None;
+    let mut goal = None;
+    for (key, body) in sections {
+        let puzzle = parse_any(&body).map_err(|e| format!("in {} section : {}", key, e))?;
+
+        match &key as &str {
+            "start" => start = Some(puzzle),
+            _ => goal = Some(puzzle),
+        }
+    }
+
+    let start = match start {
+        Some(s) => s,
+        None => return Err(ERR_START_MISSING.to_string()),
+    };
+    if goal.as_ref().is_some_and(|g| g.size != start.size) {
+        return Err(ERR_SIZES_DIFFER.to_string());
+    }
+
+    let labeled = LabeledPuzzle { start, goal, metadata };
+    match labeled.get("length") {
+        Some(l) if l.parse::<usize>().is_err() => Err(format!("{} : {}", ERR_LENGTH_INVALID, l)),
+        _ => Ok(labeled),
+    }
+}
+
+#[cfg(feature = "native")]
+fn read_input(file: Option<&String>) -> (String, String) {
+    let filename = match file {
+        Some(f) => f.clone(),
+        None => "stdin".to_string(),
+    };
+    let content = match file {
+        Some(f) => fs::read_to_string(f).map_err(|_| ERR_OPEN_FILE),
+        None => {
+            let mut s = String::new();
+            io::stdin().read_to_string(&mut s).map(|_| s).map_err(|_| ERR_IO_STDIN)
+        }
+    };
+    match content {
+        Ok(s) => (filename, s),
+        Err(e) => {
+            println!("File : {} : {}", filename, e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+fn parse_content_as(content: &str, filename: &String, format: Option<&str>) -> Option<ParsedPuzzle> {
+    let format = format.unwrap_or_else(|| detect_format(content));
+    //the 42 format reports every error, not only the first one
+    if format == "text" {
+        return generate_puzzle(&content.to_string(), filename);
+    }
+
+    let result = match format_from_name(format) {
+        Some(parser) => parser(content),
+        None => Err(format!("{} : {}", ERR_UNKNOWN_FORMAT, format)),
+    };
+    match result {
+        Ok(puzzle) => Some(puzzle),
+        Err(e) => {
+            println!("File : {} : {}", filename, e);
+            None
+        }
+    }
+}
+
+// Reads a puzzle from a file or from stdin, the format being guessed when not
+// given. Errors are printed along with the file name.
+#[cfg(feature = "native")]
+pub fn parse_as(file: Option<&String>, format: Option<&str>) -> Option<ParsedPuzzle> {
+    let (filename, content) = read_input(file);
+
+    parse_content_as(&content, &filename, format)
+}
+
+// Same as parse_as, reading labeled sections when the content has some
+#[cfg(feature = "native")]
+pub fn parse_labeled_as(file: Option<&String>, format: Option<&str>) -> Option<LabeledPuzzle> {
+    let (filename, content) = read_input(file);
+
+    if !is_labeled(&content) {
+        return parse_content_as(&content, &filename, format).map(|start| LabeledPuzzle {
+            start,
+            goal: None,
+            metadata: Vec::new(),
+        });
+    }
+    match parse_labeled(&content) {
+        Ok(labeled) => Some(labeled),
+        Err(e) => {
+            println!("File : {} : {}", filename, e);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn parse(file: Option<&String>) -> Option<ParsedPuzzle> {
+    parse_as(file, None)
 }
 
 #[cfg(test)]
@@ -340,6 +857,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "native")]
     fn is_puzzle_correct_ok() {
         let puzzle = ParsedPuzzle {
             container: vec![0, 1, 2, 3, 4, 5, 6, 7, 8],
@@ -351,6 +869,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "native")]
     fn is_puzzle_correct_line_failed_number_of_line() {
         let puzzle = ParsedPuzzle {
             container: vec![0, 1, 2, 3, 4, 5],
@@ -362,6 +881,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "native")]
     fn is_puzzle_correct_failed_superior_to_max_number() {
         let puzzle = ParsedPuzzle {
             container: vec![0, 1, 2, 3, 4, 5, 6, 11, 10],
@@ -373,6 +893,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "native")]
     fn is_puzzle_correct_failed_duplicated_value() {
         let puzzle = ParsedPuzzle {
             container: vec![0, 1, 2, 3, 4, 3, 6, 7, 8],
@@ -432,4 +953,275 @@ mod tests {
         assert_ne!(vec_err, None);
         assert_eq!(vec_err.unwrap(), vec![ERR_NUMBER_INVALID]);
     }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn generate_batch_blank_lines() {
+        let content = "3\n1 2 3\n4 5 6\n7 8 0\n\n3\n0 1 2\n3 4 5\n6 7 8\n";
+        let batch = generate_batch(content, "batch");
+
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].0, "batch:1");
+        assert_eq!(batch[1].0, "batch:6");
+        assert_eq!(batch[1].1.as_ref().unwrap().container, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn generate_batch_headers() {
+        let content = "## first\n3\n1 2 3\n4 5 6\n7 8 0\n## second\n3\n0 1 2\n3 4 5\n6 7 8";
+        let batch = generate_batch(content, "batch");
+        let names: Vec<&str> = batch.iter().map(|e| &e.0 as &str).collect();
+
+        assert_eq!(names, vec!["first", "second"]);
+        assert!(batch.iter().all(|e| e.1.is_some()));
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn generate_batch_blank_inside_puzzle() {
+        let content = "3 #size\n\n4 5 6\n\n1 2 3\n\n7 8 0\n#end";
+        let batch = generate_batch(content, "batch");
+
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].1.as_ref().unwrap().container, vec![4, 5, 6, 1, 2, 3, 7, 8, 0]);
+    }
+
+    #[test]
+    #[cfg(feature = "native")]
+    fn generate_batch_keeps_going() {
+        let content = "toto\n1 2 3\n\n4\n1 2 3 4\n\n3\n1 2 3\n4 5 6\n7 8 0";
+        let batch = generate_batch(content, "batch");
+
+        assert_eq!(batch.len(), 3);
+        assert!(batch[0].1.is_none());
+        assert!(batch[1].1.is_none());
+        assert!(batch[2].1.is_some());
+    }
+
+    #[test]
+    fn parse_compact_ok() {
+        let puzzle = parse_compact("3 1 2 3 4 5 6 7 8 0 # comment").unwrap().unwrap();
+
+        assert_eq!(puzzle.size, 3);
+        assert_eq!(puzzle.container, vec![1, 2, 3, 4, 5, 6, 7, 8, 0]);
+    }
+
+    #[test]
+    fn parse_compact_blank() {
+        assert!(parse_compact("").unwrap().is_none());
+        assert!(parse_compact("   # only a comment").unwrap().is_none());
+    }
+
+    #[test]
+    fn parse_compact_errors() {
+        assert_eq!(parse_compact("2 1 2 3 0").err(), Some(ERR_SIZE.to_string()));
+        assert_eq!(parse_compact("65535 1").err(), Some(ERR_SIZE_INVALID.to_string()));
+        assert_eq!(parse_compact("3 1 2 3").err(), Some(ERR_NUMBER_OF_LINES.to_string()));
+        assert!(parse_compact("3 1 2 3 4 5 6 7 8 x").is_err());
+        assert!(parse_compact("3 1 2 3 4 5 6 7 8 8").is_err());
+    }
+
+    #[test]
+    fn parse_str_ok() {
+        let puzzle = parse_str("# comment\n3\n1 2 3\n4 5 6\n7 8 0\n").unwrap();
+
+        assert_eq!(puzzle.container, vec![1, 2, 3, 4, 5, 6, 7, 8, 0]);
+    }
+
+    #[test]
+    fn parse_str_errors() {
+        assert_eq!(
+            parse_str("3\n1 2 3\n4 x 6\n7 8 0\n").err(),
+            Some(format!("at line 3 : {}", ERR_NUMBER_INVALID))
+        );
+        assert_eq!(
+            parse_str("3\n1 2 3\n4 5 6\n").err(),
+            Some(ERR_NUMBER_OF_LINES.to_string())
+        );
+    }
+
+    //found by fuzzing: room for size * size tiles was reserved before the size
+    //was checked, and size * size overflowed an Atom in puzzle_error
+    #[test]
+    fn parse_str_huge_sizes() {
+        for content in ["65535\n", "9545  ", "256\n1 2 3\n", "101\n"].iter() {
+            assert_eq!(parse_str(content).err(), Some(format!("at line 1 : {}", ERR_SIZE_INVALID)));
+        }
+        assert!(parse_str(&format!("100\n{}", "0 ".repeat(100))).is_err());
+    }
+
+    #[test]
+    fn puzzle_error_huge_size() {
+        let puzzle = ParsedPuzzle {
+            container: vec![0; 9],
+            size: 256,
+        };
+
+        assert_eq!(puzzle_error(&puzzle), Some(ERR_SIZE_INVALID.to_string()));
+    }
+
+    const SNAIL: [Atom; 9] = [1, 2, 3, 8, 0, 4, 7, 6, 5];
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(detect_format("3\n1 2 3\n8 0 4\n7 6 5\n"), "text");
+        assert_eq!(detect_format("  {\"size\": 3}"), "json");
+        assert_eq!(detect_format("# a: b, c\n3:1,2,3,8,0,4,7,6,5\n"), "line");
+        assert_eq!(detect_format("1,2,3\n8,0,4\n7,6,5\n"), "csv");
+        assert_eq!(detect_format(""), "text");
+    }
+
+    #[test]
+    fn every_format_reads_the_same_puzzle() {
+        let inputs = [
+            "# snail\n3\n1 2 3\n8 0 4\n7 6 5\n",
+            "{\"size\": 3, \"tiles\": [1, 2, 3, 8, 0, 4, 7, 6, 5]}",
+            "{\"name\": \"s\\\"n\", \"tiles\": [[1, 2, 3], [8, 0, 4], [7, 6, 5]], \"size\": 3, \"x\": null}",
+            "3:1,2,3,8,0,4,7,6,5",
+            "# snail\n1, 2, 3\n8, 0, 4\n\n7, 6, 5\n",
+        ];
+
+        for input in inputs.iter() {
+            if detect_format(input) == "json" && !cfg!(feature = "serde") {
+                assert_eq!(parse_any(input).err(), Some(ERR_JSON_FEATURE.to_string()));
+                continue;
+            }
+            let puzzle = parse_any(input).unwrap();
+
+            assert_eq!(puzzle.size, 3, "{}", input);
+            assert_eq!(puzzle.container, SNAIL.to_vec(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn format_names() {
+        assert!(format_from_name("csv").is_some());
+        assert!(format_from_name("yaml").is_none());
+        assert!(format_from_name("json").unwrap()("3:1,2,3,8,0,4,7,6,5").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn parse_json_errors() {
+        assert_eq!(parse_json("[1, 2]").err(), Some(ERR_JSON_FIELDS.to_string()));
+        assert_eq!(parse_json("{\"size\": 3}").err(), Some(ERR_JSON_FIELDS.to_string()));
+        assert_eq!(parse_json("{\"size\": 2, \"tiles\": []}").err(), Some(ERR_SIZE.to_string()));
+        assert_eq!(
+            parse_json("{\"size\": 65535, \"tiles\": []}").err(),
+            Some(ERR_SIZE_INVALID.to_string())
+        );
+        assert_eq!(
+            parse_json("{\"size\": 3, \"tiles\": [[1, 2], [3]]}").err(),
+            Some(ERR_NUMBER_PER_LINE.to_string())
+        );
+        assert_eq!(
+            parse_json("{\"size\": 3, \"tiles\": [1, 2, 3]}").err(),
+            Some(ERR_NUMBER_OF_LINES.to_string())
+        );
+        assert!(parse_json("{\"size\": 3, \"tiles\": [1, 1, 3, 8, 0, 4, 7, 6, 5]}").is_err());
+        assert!(parse_json("{\"size\": 3, \"tiles\": [1.5]}").is_err());
+        assert!(parse_json("{\"size\": 3,}").err().unwrap().starts_with(ERR_JSON_SYNTAX));
+        assert!(parse_json("{\"size\": 3} 4").err().unwrap().starts_with(ERR_JSON_SYNTAX));
+        assert!(parse_json("{\"size").err().unwrap().starts_with(ERR_JSON_SYNTAX));
+        assert!(parse_json(&"[".repeat(100000)).err().unwrap().starts_with(ERR_JSON_SYNTAX));
+    }
+
+    #[test]
+    fn parse_single_line_errors() {
+        assert_eq!(parse_single_line("3").err(), Some(ERR_SINGLE_LINE.to_string()));
+        assert_eq!(parse_single_line("3:1\n3:1").err(), Some(ERR_SINGLE_LINE.to_string()));
+        assert_eq!(
+            parse_single_line("\n1:1,2,3").err(),
+            Some(format!("at line 2 : {}", ERR_SIZE))
+        );
+        assert_eq!(
+            parse_single_line("3:1,2,x").err(),
+            Some(format!("at line 1 : {} : x", ERR_NUMBER_INVALID))
+        );
+        assert_eq!(
+            parse_single_line("3:1,2,3,8,0,4,7,6,9").err().map(|e| e.starts_with(ERR_INVALID_ELMT)),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn parse_csv_errors() {
+        assert_eq!(
+            parse_csv("1,2,3\n8,0\n7,6,5").err(),
+            Some(format!("at line 2 : {}", ERR_NUMBER_PER_LINE))
+        );
+        assert_eq!(parse_csv("0,1\n2,3").err(), Some(ERR_SIZE.to_string()));
+        assert_eq!(
+            parse_csv("1,2,3\n8,0,4\n7,6,6").err().map(|e| e.starts_with(ERR_DUPLICATED_VALUE)),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn parse_labeled_ok() {
+        let labeled = parse_labeled(
+            "# regression\nname: snail swap\nlength: 1\n\nstart:\n3\n1 2 3\n8 4 0\n7 6 5\n\ngoal: 3:1,2,3,8,0,4,7,6,5\n",
+        )
+        .unwrap();
+
+        assert_eq!(labeled.start.container, vec![1, 2, 3, 8, 4, 0, 7, 6, 5]);
+        assert_eq!(labeled.goal.as_ref().unwrap().container, SNAIL.to_vec());
+        assert_eq!(labeled.get("name"), Some("snail swap"));
+        assert_eq!(labeled.expected_length(), Some(1));
+    }
+
+    #[test]
+    fn parse_labeled_start_only() {
+        let content = "start:\n3:1,2,3,8,0,4,7,6,5\n";
+        let labeled = parse_labeled(content).unwrap();
+
+        assert!(is_labeled(content));
+        assert!(labeled.goal.is_none());
+        assert!(labeled.metadata.is_empty());
+        assert_eq!(labeled.expected_length(), None);
+    }
+
+    #[test]
+    fn parse_labeled_errors() {
+        assert!(!is_labeled("3:1,2,3,8,0,4,7,6,5"));
+        assert!(!is_labeled("# start:\n3\n1 2 3\n8 0 4\n7 6 5\n"));
+        assert_eq!(
+            parse_labeled("goal:\n3:1,2,3,8,0,4,7,6,5").err(),
+            Some(ERR_START_MISSING.to_string())
+        );
+        assert_eq!(
+            parse_labeled("3\nstart:\n").err(),
+            Some(format!("at line 1 : {}", ERR_OUTSIDE_SECTION))
+        );
+        assert_eq!(
+            parse_labeled("start: 3:1,2,3,8,0,4,7,6,5\nstart:").err(),
+            Some(format!("at line 2 : {} : start", ERR_SECTION_TWICE))
+        );
+        assert_eq!(
+            parse_labeled("start:\n3\n1 2 3\n8 x 4\n7 6 5\n").err(),
+            Some(format!("in start section : at line 4 : {}", ERR_NUMBER_INVALID))
+        );
+        assert_eq!(
+            parse_labeled("start: 3:1,2,3,8,0,4,7,6,5\ngoal: 4:1,2,3,4,12,13,14,5,11,0,15,6,10,9,8,7").err(),
+            Some(ERR_SIZES_DIFFER.to_string())
+        );
+        assert_eq!(
+            parse_labeled("length: many\nstart: 3:1,2,3,8,0,4,7,6,5").err(),
+            Some(format!("{} : many", ERR_LENGTH_INVALID))
+        );
+    }
+
+    #[test]
+    fn puzzle_error_duplicated_value() {
+        let puzzle = ParsedPuzzle {
+            container: vec![1, 2, 3, 4, 5, 7, 7, 8, 0],
+            size: 3,
+        };
+
+        assert_eq!(
+            puzzle_error(&puzzle),
+            Some(format!("{} : 7 at puzzle index 6", ERR_DUPLICATED_VALUE))
+        );
+    }
 }
@@ -1,8 +1,34 @@
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate rmp_serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+#[cfg(feature = "native")]
+pub mod bench;
+pub mod cancel;
 pub mod checker;
+pub mod clock;
+pub mod diagnostics;
+pub mod export;
+#[cfg(feature = "native")]
+pub mod external;
+#[cfg(feature = "native")]
+pub mod ffi;
+pub mod game;
 pub mod generator;
 pub mod heuristics;
 pub mod input_parser;
+pub mod observer;
+#[cfg(test)]
+mod properties;
 pub mod solver;
+pub mod state_space;
+pub mod trace;
 pub mod types;
+pub mod writer;
 
 mod util;
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use clock;
+use clock::Clock;
+
+use types::Result;
+use types::Statistic;
+
+// Snapshot of a running search
+pub struct Progress {
+    pub f_bound: Result,
+    pub expanded: Statistic,
+    pub open_states: Statistic,
+    pub closed_states: Statistic,
+    pub nodes_per_second: f64,
+    pub elapsed: Duration,
+}
+
+pub trait Observer {
+    fn progress(&mut self, progress: &Progress);
+}
+
+impl<F: FnMut(&Progress)> Observer for F {
+    fn progress(&mut self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+// Calls an observer every `every` expansions or every `interval`, whichever
+// comes first. Zero disables either of them.
+pub struct Reporter {
+    pub observer: Box<dyn Observer>,
+    pub every: Statistic,
+    pub interval: Duration,
+    clock: Clock,
+    start: Duration,
+    last_time: Duration,
+    last_expanded: Statistic,
+}
+
+impl Reporter {
+    pub fn new(observer: Box<dyn Observer>, every: Statistic, interval: Duration) -> Reporter {
+        Reporter::with_clock(observer, every, interval, clock::DEFAULT)
+    }
+
+    pub fn with_clock(
+        observer: Box<dyn Observer>,
+        every: Statistic,
+        interval: Duration,
+        clock: Clock,
+    ) -> Reporter {
+        let now = clock();
+
+        Reporter {
+            observer,
+            every,
+            interval,
+            clock,
+            start: now,
+            last_time: now,
+            last_expanded: 0,
+        }
+    }
+
+    pub fn tick(&mut self, f_bound: Result, expanded: Statistic, open: Statistic, closed: Statistic) {
+        let by_count = self.every > 0 && expanded - self.last_expanded >= self.every;
+        let by_time = self.interval > Duration::from_secs(0)
+            && clock::due(expanded)
+            && clock::since(self.clock, self.last_time) >= self.interval;
+
+        if by_count || by_time {
+            self.report(f_bound, expanded, open, closed);
+        }
+    }
+
+    pub fn report(&mut self, f_bound: Result, expanded: Statistic, open: Statistic, closed: Statistic) {
+        let now = (self.clock)();
+        let since = now.checked_sub(self.last_time).unwrap_or_default().as_secs_f64();
+        let nodes_per_second = if since > 0.0 {
+            (expanded - self.last_expanded) as f64 / since
+        } else {
+            0.0
+        };
+
+        self.observer.progress(&Progress {
+            f_bound,
+            expanded,
+            open_states: open,
+            closed_states: closed,
+            nodes_per_second,
+            elapsed: now.checked_sub(self.start).unwrap_or_default(),
+        });
+
+        self.last_time = now;
+        self.last_expanded = expanded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn tick_every() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink = seen.clone();
+        let mut reporter = Reporter::new(
+            Box::new(move |p: &Progress| sink.borrow_mut().push(p.expanded)),
+            10,
+            Duration::from_secs(0),
+        );
+
+        for i in 1..35 {
+            reporter.tick(0, i, 0, i);
+        }
+
+        assert_eq!(*seen.borrow(), vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn tick_disabled() {
+        let seen = Rc::new(RefCell::new(0));
+        let sink = seen.clone();
+        let mut reporter = Reporter::new(
+            Box::new(move |_: &Progress| *sink.borrow_mut() += 1),
+            0,
+            Duration::from_secs(3600),
+        );
+
+        for i in 1..1000 {
+            reporter.tick(0, i, 0, i);
+        }
+
+        assert_eq!(*seen.borrow(), 0);
+    }
+}
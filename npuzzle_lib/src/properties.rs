@@ -0,0 +1,209 @@
+// Properties every solver and heuristic has to hold, checked against random
+// solvable states. Seeds are fixed, so that a failure can be replayed.
+
+#[cfg(feature = "native")]
+use external;
+#[cfg(feature = "native")]
+use external::DiskConfig;
+use generator;
+use heuristics::HEURISTICS;
+use solver;
+use solver::SOLVERS;
+use state_space;
+use state_space::DistanceTable;
+
+use types::Atom;
+use types::Heuristic;
+use types::ParsedPuzzle;
+use types::Problem;
+use types::Puzzle;
+use types::SearchOptions;
+use types::Solution;
+use types::Status;
+
+use util::find_empty_pos;
+
+// A random walk from the goal always ends on a state that can reach it back.
+// 3x3 walks are long enough to spread over the whole state space, 4x4 ones are
+// kept short for every heuristic to solve them in a debug build.
+const WALK_3X3: u64 = 1000;
+const WALK_4X4: u64 = 40;
+const SAMPLES_3X3: u64 = 2;
+const SAMPLES_4X4: u64 = 4;
+
+// Every how many permutations a heuristic is checked against the table
+const RANK_STEP: usize = 37;
+
+pub fn random_states(goal: &Puzzle, size: Atom, iterations: u64, samples: u64) -> Vec<Puzzle> {
+    let goal = ParsedPuzzle {
+        container: goal.clone(),
+        size,
+    };
+
+    (0..samples)
+        .map(|seed| generator::generate_seeded_puzzle(&goal, iterations, seed).container)
+        .collect()
+}
+
+pub fn random_3x3(samples: u64) -> Vec<Puzzle> {
+    random_states(&generator::snail(3), 3, WALK_3X3, samples)
+}
+
+pub fn random_4x4(samples: u64) -> Vec<Puzzle> {
+    random_states(&generator::classic(4), 4, WALK_4X4, samples)
+}
+
+fn problem(start: &Puzzle, goal: &Puzzle, size: Atom, heuristic: Heuristic, h_weight: u64) -> Problem {
+    Problem {
+        start: start.clone(),
+        end: goal.clone(),
+        size,
+        heuristic,
+        g_weight: 1,
+        h_weight,
+    }
+}
+
+// The path starts on the start, ends on the goal, and the blank moves by
+// exactly one cell at each step
+fn assert_legal(solution: &Solution, name: &str) {
+    let problem = &solution.problem;
+    let path = &solution.path;
+
+    assert_eq!(solution.status, Status::Solved, "{}", name);
+    assert_eq!(path.first(), Some(&problem.start), "{}", name);
+    assert_eq!(path.last(), Some(&problem.end), "{}", name);
+    for w in path.windows(2) {
+        let moves = solver::neighbors(&w[0], find_empty_pos(&w[0]), problem.size);
+
+        assert!(moves.iter().any(|m| m.0 == w[1]), "{}: illegal move in path", name);
+    }
+}
+
+// Solves with every optimal solver and gives the length each one found
+fn optimal_lengths(start: &Puzzle, goal: &Puzzle, size: Atom) -> Vec<(String, usize)> {
+    let mut lengths = Vec::new();
+    let mut record = |name: String, solution: Solution| {
+        assert_legal(&solution, &name);
+        lengths.push((name, solution.path.len() - 1));
+    };
+
+    for &(name, heuristic) in HEURISTICS.iter() {
+        let p = problem(start, goal, size, heuristic, 1);
+        record(format!("astar {}", name), solver::astar(&p));
+    }
+    for &(name, solve) in SOLVERS.iter() {
+        let p = problem(start, goal, size, HEURISTICS[0].1, 1);
+        record(format!("solver {}", name), solve(&p, &mut SearchOptions::default()));
+    }
+    #[cfg(feature = "native")]
+    {
+        let p = problem(start, goal, size, HEURISTICS[0].1, 1);
+        let solution = external::astar(&p, &DiskConfig::default(), &mut SearchOptions::default()).unwrap();
+        record("external astar".to_string(), solution);
+    }
+    if size == 3 {
+        let p = problem(start, goal, size, HEURISTICS[0].1, 0);
+        record("uniform cost".to_string(), solver::astar(&p));
+    }
+
+    lengths
+}
+
+fn assert_same_lengths(lengths: &[(String, usize)], start: &Puzzle) {
+    for &(ref name, length) in lengths.iter() {
+        assert_eq!(length, lengths[0].1, "{} and {} disagree on {:?}", name, lengths[0].0, start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generators_give_solvable_states() {
+        use checker;
+
+        for start in random_3x3(SAMPLES_3X3) {
+            assert!(checker::is_solvable(&start, &generator::snail(3), 3));
+        }
+        for start in random_4x4(SAMPLES_4X4) {
+            assert!(checker::is_solvable(&start, &generator::classic(4), 4));
+        }
+        assert_ne!(random_3x3(2)[0], random_3x3(2)[1]);
+    }
+
+    #[test]
+    fn solvers_agree_3x3() {
+        let goal = generator::snail(3);
+        let table = DistanceTable::build(&goal);
+
+        for start in random_3x3(SAMPLES_3X3) {
+            let mut lengths = optimal_lengths(&start, &goal, 3);
+
+            lengths.push(("bfs".to_string(), table.distance(&start).unwrap() as usize));
+            assert_same_lengths(&lengths, &start);
+        }
+    }
+
+    #[test]
+    fn solvers_agree_4x4() {
+        let goal = generator::classic(4);
+
+        for start in random_4x4(SAMPLES_4X4) {
+            assert_same_lengths(&optimal_lengths(&start, &goal, 4), &start);
+        }
+    }
+
+    // Uniform cost search expands most of the state space that far from the goal,
+    // run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn hard_3x3_optimal() {
+        let start: Puzzle = vec![7, 1, 8, 5, 2, 6, 3, 4, 0];
+        let lengths = optimal_lengths(&start, &generator::classic(3), 3);
+
+        assert_same_lengths(&lengths, &start);
+        assert_eq!(lengths[0].1, 24);
+    }
+
+    // Admissible: never more than the moves left. Consistent: never drops by more
+    // than one over a move, which A* relies on to expand every state only once.
+    // Both 3x3 state spaces are walked, run with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn heuristics_admissible_and_consistent() {
+        for goal in [generator::snail(3), generator::classic(3)].iter() {
+            let table = DistanceTable::build(goal);
+
+            for rank in (0..(1..goal.len() + 1).product::<usize>()).step_by(RANK_STEP) {
+                let state = state_space::unrank(rank, goal.len());
+                let distance = match table.distance(&state) {
+                    Some(d) => d as u64,
+                    None => continue,
+                };
+                let moves = solver::neighbors(&state, find_empty_pos(&state), 3);
+
+                for &(name, heuristic) in HEURISTICS.iter() {
+                    let h = heuristic.eval(&state, goal, 3);
+
+                    assert!(h <= distance, "{} gives {} for {:?}, {} moves away", name, h, state, distance);
+                    for m in moves.iter() {
+                        assert!(h <= heuristic.eval(&m.0, goal, 3) + 1, "{} is not consistent on {:?}", name, state);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn heuristics_zero_on_goal() {
+        for size in 3..6 {
+            for goal in [generator::snail(size), generator::classic(size)].iter() {
+                for &(name, heuristic) in HEURISTICS.iter() {
+                    assert_eq!(heuristic.eval(goal, goal, size), 0, "{}", name);
+                }
+            }
+        }
+    }
+}
@@ -1,20 +1,34 @@
+use std::borrow::Cow;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::time::SystemTime;
+use std::time::Duration;
+
+use clock;
+use clock::Clock;
 
 use types::Atom;
 use types::Node;
 use types::Problem;
 use types::Puzzle;
+use types::Result;
+use types::SearchOptions;
 use types::Solution;
+use types::Solver;
+use types::Statistic;
+use types::Status;
+
+use trace::Expansion;
 
 use util::find_empty_pos;
+#[cfg(feature = "native")]
 use util::print_puzzle;
 use util::xy;
 
 const NEIGHBOR_DELTAS: [(i8, i8); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
 
+pub static SOLVERS: [(&str, Solver); 1] = [("astar", astar_with)];
+
 pub fn neighbors(puzzle: &Puzzle, pos: Atom, size: Atom) -> HashSet<(Puzzle, Atom)> {
     let mut set = HashSet::new();
 
@@ -48,99 +62,307 @@ pub fn neighbors(puzzle: &Puzzle, pos: Atom, size: Atom) -> HashSet<(Puzzle, Ato
     set
 }
 
-pub fn astar<'a>(problem: &'a Problem) -> Solution {
-    let start_time = SystemTime::now();
-
-    let mut open = BinaryHeap::new();
-    let mut closed = HashSet::new();
-    let mut from: HashMap<Puzzle, Puzzle> = HashMap::new();
+pub fn astar(problem: &Problem) -> Solution<'_> {
+    astar_with(problem, &mut SearchOptions::default())
+}
 
-    // Final path
-    let mut path = Vec::new();
+// Outcome of a single expansion
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Step {
+    Expanded,
+    Solved,
+    Exhausted,
+}
 
-    // Add the first node
-    let initial_h_result = (problem.heuristic)(&problem.start, &problem.end, problem.size);
+// Limits on a search, None meaning unbounded
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Budget {
+    pub expanded: Option<Statistic>,
+    pub time: Option<Duration>,
+}
 
-    open.push(Node {
-        array: problem.start.clone(),
-        h_result: initial_h_result,
-        g_result: 0,
-        f_result: initial_h_result,
-        pos: find_empty_pos(&problem.start),
-    });
+// Why a search stopped before it ended
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Limit {
+    Expanded,
+    Time,
+    Cancelled,
+}
 
-    // start poppin' nodes
-    let mut node_wrapped = open.pop();
-    let mut node;
+// A* search driven one expansion at a time, so that a caller can look at the
+// frontier between steps
+pub struct Search<'a> {
+    problem: &'a Problem,
+    open: BinaryHeap<Node>,
+    closed: HashSet<Puzzle>,
+    from: HashMap<Puzzle, Puzzle>,
+    g_results: HashMap<Puzzle, Result>,
+    best: Option<Node>,
+    expanded: Statistic,
+    // states pushed into open, and the peak of open and closed together
+    opened: Statistic,
+    max_states: Statistic,
+    status: Option<Status>,
+    limit: Option<Limit>,
+    path: Vec<Puzzle>,
+    clock: Clock,
+    start_time: Duration,
+}
 
-    while node_wrapped != None {
-        node = node_wrapped.unwrap();
+impl<'a> Search<'a> {
+    pub fn new(problem: &'a Problem) -> Search<'a> {
+        Search::with_clock(problem, clock::DEFAULT)
+    }
 
-        closed.insert(node.array.clone());
+    pub fn with_clock(problem: &'a Problem, clock: Clock) -> Search<'a> {
+        let mut open = BinaryHeap::new();
+
+        // Add the first node
+        let initial_h_result = problem.heuristic.eval(&problem.start, &problem.end, problem.size);
+        let mut g_results = HashMap::new();
+
+        g_results.insert(problem.start.clone(), 0);
+
+        open.push(Node {
+            array: problem.start.clone(),
+            h_result: initial_h_result,
+            g_result: 0,
+            f_result: initial_h_result,
+            pos: find_empty_pos(&problem.start),
+        });
+
+        Search {
+            problem,
+            open,
+            closed: HashSet::new(),
+            from: HashMap::new(),
+            g_results,
+            best: None,
+            expanded: 0,
+            opened: 1,
+            max_states: 1,
+            status: None,
+            limit: None,
+            path: Vec::new(),
+            clock,
+            start_time: clock(),
+        }
+    }
 
-        if node.array == problem.end {
-            // Done, time to unwind the path
-            let mut current = node.array.clone();
+    pub fn step(&mut self) -> Step {
+        self.step_with(&mut SearchOptions::default())
+    }
 
-            path.push(current.clone());
-            while from.contains_key(&current) {
-                current = from[&current].clone();
-                path.push(current.clone());
+    // Expands the next node of the frontier. Once the search is over, further
+    // calls do nothing and keep returning how it ended.
+    pub fn step_with(&mut self, options: &mut SearchOptions) -> Step {
+        match self.status {
+            Some(Status::Solved) => return Step::Solved,
+            Some(_) => return Step::Exhausted,
+            None => {}
+        }
+        self.limit = None;
+
+        // a state is pushed again whenever a shorter way to it is found, the
+        // copies left behind are skipped once it has been expanded
+        let node = loop {
+            match self.open.pop() {
+                Some(ref n) if self.closed.contains(&n.array) => continue,
+                Some(n) => break n,
+                None => {
+                    self.status = Some(Status::Unsolvable);
+                    return Step::Exhausted;
+                }
             }
-            break;
+        };
+
+        self.closed.insert(node.array.clone());
+        self.expanded += 1;
+
+        if let Some(ref mut trace) = options.trace {
+            trace.record(Expansion {
+                order: self.expanded - 1,
+                state: node.array.clone(),
+                parent: self.from.get(&node.array).cloned(),
+                g_result: node.g_result,
+                h_result: node.h_result,
+                f_result: node.f_result,
+            });
+        }
+
+        if let Some(ref mut reporter) = options.reporter {
+            reporter.tick(node.f_result, self.expanded, self.open_count(), self.closed.len());
         }
 
-        for raw_neighbor in neighbors(&node.array, node.pos, problem.size) {
+        if node.array == self.problem.end {
+            // Done, time to unwind the path
+            self.path = self.path_to(&node.array);
+            self.status = Some(Status::Solved);
+            self.best = Some(node);
+            return Step::Solved;
+        }
+
+        for raw_neighbor in neighbors(&node.array, node.pos, self.problem.size) {
             let (neighbor, neighbor_pos) = raw_neighbor;
 
-            if closed.contains(&neighbor) {
+            if self.closed.contains(&neighbor) {
                 continue;
             }
 
             let g_result = node.g_result + 1;
-            let h_result = (problem.heuristic)(&neighbor, &problem.end, problem.size);
+            if self.g_results.get(&neighbor).is_some_and(|&known| known <= g_result) {
+                continue;
+            }
 
-            let f_result = (h_result * problem.h_weight) + (g_result * problem.g_weight);
+            let h_result = self.problem.heuristic.eval(&neighbor, &self.problem.end, self.problem.size);
 
-            open.push(Node {
+            let f_result = (h_result * self.problem.h_weight) + (g_result * self.problem.g_weight);
+
+            self.open.push(Node {
                 array: neighbor.clone(),
                 h_result,
                 g_result,
                 f_result,
                 pos: neighbor_pos,
             });
+            self.opened += 1;
 
-            from.insert(neighbor.clone(), node.array.clone());
+            self.g_results.insert(neighbor.clone(), g_result);
+            self.from.insert(neighbor, node.array.clone());
+        }
+        self.max_states = ::std::cmp::max(self.max_states, self.open.len() + self.closed.len());
+
+        let closer = match self.best {
+            Some(ref b) => (node.h_result, node.g_result) < (b.h_result, b.g_result),
+            None => true,
+        };
+        if closer {
+            self.best = Some(node);
         }
 
-        node_wrapped = open.pop();
+        Step::Expanded
     }
 
-    path.reverse();
+    // Steps until the search ends, giving None, or until it runs out of budget
+    pub fn run(&mut self, options: &mut SearchOptions, budget: &Budget) -> Option<Limit> {
+        self.limit = self.run_until(options, budget);
+        self.limit
+    }
 
-    // done
-    Solution {
-        problem,
-        path,
-        max_states: closed.len() + open.len(),
-        opened_states: open.len() + closed.len(),
-        current_open_states: open.len(),
-        closed_states: closed.len(),
-        time: start_time,
+    fn run_until(&mut self, options: &mut SearchOptions, budget: &Budget) -> Option<Limit> {
+        let started = (self.clock)();
+
+        loop {
+            if options.is_cancelled() {
+                return Some(Limit::Cancelled);
+            }
+            if self.step_with(options) != Step::Expanded {
+                return None;
+            }
+            if budget.expanded.is_some_and(|max| self.expanded >= max) {
+                return Some(Limit::Expanded);
+            }
+            if clock::due(self.expanded) && budget.time.is_some_and(|max| clock::since(self.clock, started) >= max) {
+                return Some(Limit::Time);
+            }
+        }
+    }
+
+    // Open nodes, the next one to be expanded first. The heap also keeps the
+    // copies left behind by a shorter way to a state, those are not listed.
+    pub fn frontier(&self) -> Vec<&Node> {
+        let mut nodes: Vec<&Node> = self
+            .open
+            .iter()
+            .filter(|n| !self.closed.contains(&n.array) && self.g_results.get(&n.array) == Some(&n.g_result))
+            .collect();
+
+        nodes.sort_by(|a, b| b.cmp(a));
+
+        nodes
+    }
+
+    pub fn peek(&self) -> Option<&Node> {
+        self.open.peek()
+    }
+
+    // Expanded node closest to the goal according to the heuristic
+    pub fn best(&self) -> Option<&Node> {
+        self.best.as_ref()
+    }
+
+    pub fn expanded(&self) -> Statistic {
+        self.expanded
+    }
+
+    // Every state given a cost is either closed or still waiting in open
+    pub fn open_count(&self) -> Statistic {
+        self.g_results.len() - self.closed.len()
+    }
+
+    pub fn closed_count(&self) -> Statistic {
+        self.closed.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status.is_some()
+    }
+
+    // Moves leading from the start to a state reached so far
+    pub fn path_to(&self, state: &Puzzle) -> Vec<Puzzle> {
+        let mut current = state.clone();
+        let mut path = vec![current.clone()];
+
+        while let Some(parent) = self.from.get(&current) {
+            current = parent.clone();
+            path.push(current.clone());
+        }
+        path.reverse();
+
+        path
+    }
+
+    // A search stopped before it ended is out of budget when run hit one of its
+    // limits, cancelled when asked to, and still in progress when stepped by hand
+    pub fn solution(&self) -> Solution<'a> {
+        let stopped = match self.limit {
+            Some(Limit::Expanded) | Some(Limit::Time) => Status::OutOfBudget,
+            Some(Limit::Cancelled) => Status::Cancelled,
+            None => Status::InProgress,
+        };
+
+        Solution {
+            problem: Cow::Borrowed(self.problem),
+            status: self.status.unwrap_or(stopped),
+            path: self.path.clone(),
+            max_states: self.max_states,
+            opened_states: self.opened,
+            current_open_states: self.open_count(),
+            closed_states: self.closed.len(),
+            time: clock::since(self.clock, self.start_time),
+        }
     }
 }
 
+pub fn astar_with<'a>(problem: &'a Problem, options: &mut SearchOptions) -> Solution<'a> {
+    let mut search = Search::with_clock(problem, options.clock);
+
+    search.run(options, &Budget::default());
+    search.solution()
+}
+
+#[cfg(feature = "native")]
 pub fn print_solution(s: &Solution) {
     println!("-----------------");
     for p in &s.path {
-        print_puzzle(&p, s.problem.size);
+        print_puzzle(p, s.problem.size);
         println!("-----------------");
     }
 
-    match &s.time.elapsed() {
-        Ok(elapsed) => println!(" - Time elapsed: {:?}", elapsed),
-        Err(_) => {}
-    }
+    println!(" - Time elapsed: {:?}", s.time);
     println!(" - Solution length: {}", s.path.len() - 1);
     println!(" - Maximum states in memory: {}", s.max_states);
     println!(
@@ -151,3 +373,148 @@ pub fn print_solution(s: &Solution) {
     println!(" - Total closed states: {}", s.closed_states);
     println!("-----------------");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cancel::CancelToken;
+    use generator;
+    use trace::Trace;
+    use types::Heuristic;
+
+    #[test]
+    fn astar_with_trace() {
+        let problem = Problem {
+            start: vec![1, 2, 3, 4, 5, 6, 0, 7, 8],
+            end: generator::classic(3),
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 1,
+        };
+        let mut options = SearchOptions {
+            trace: Some(Trace::new(100)),
+            ..SearchOptions::default()
+        };
+        let solution = astar_with(&problem, &mut options);
+        let trace = options.trace.unwrap();
+
+        assert_eq!(solution.path.len(), 3);
+        assert_eq!(trace.expansions[0].state, problem.start);
+        assert_eq!(trace.expansions[0].parent, None);
+        assert_eq!(trace.expansions.last().unwrap().state, problem.end);
+        assert_eq!(trace.expansions.last().unwrap().g_result, 2);
+        assert!(!trace.truncated);
+    }
+
+    #[test]
+    fn astar_with_cancel() {
+        let problem = Problem {
+            start: vec![7, 1, 8, 5, 2, 6, 3, 4, 0],
+            end: generator::classic(3),
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 1,
+        };
+        let token = CancelToken::new();
+        let mut options = SearchOptions {
+            cancel: Some(token.clone()),
+            ..SearchOptions::default()
+        };
+
+        token.cancel();
+        let solution = astar_with(&problem, &mut options);
+
+        assert_eq!(solution.status, Status::Cancelled);
+        assert!(solution.path.is_empty());
+        assert_eq!(solution.closed_states, 0);
+    }
+
+    #[test]
+    fn search_step() {
+        let problem = Problem {
+            start: vec![1, 2, 3, 4, 5, 6, 0, 7, 8],
+            end: generator::classic(3),
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 1,
+        };
+        let mut search = Search::new(&problem);
+
+        assert_eq!(search.peek().unwrap().array, problem.start);
+        assert_eq!(search.step(), Step::Expanded);
+        assert_eq!(search.closed_count(), 1);
+        assert_eq!(search.open_count(), 2);
+        assert_eq!(search.best().unwrap().array, problem.start);
+
+        let partial = search.solution();
+        assert_eq!(partial.status, Status::InProgress);
+        assert_eq!(partial.opened_states, 3);
+        assert_eq!(partial.max_states, 3);
+        assert_eq!(partial.current_open_states, 2);
+
+        let frontier = search.frontier();
+        assert!(frontier[0].f_result <= frontier[1].f_result);
+        assert_eq!(frontier[0].array, vec![1, 2, 3, 4, 5, 6, 7, 0, 8]);
+        let frontier_head = frontier[0].array.clone();
+
+        assert_eq!(search.step(), Step::Expanded);
+        assert_eq!(search.best().unwrap().array, frontier_head);
+        assert_eq!(search.step(), Step::Solved);
+        assert!(search.is_finished());
+        assert_eq!(search.step(), Step::Solved);
+
+        let solution = search.solution();
+        assert_eq!(solution.status, Status::Solved);
+        assert_eq!(solution.path.len(), 3);
+    }
+
+    #[test]
+    fn search_unfinished() {
+        let problem = Problem {
+            start: vec![7, 1, 8, 5, 2, 6, 3, 4, 0],
+            end: generator::classic(3),
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 1,
+        };
+        let mut search = Search::new(&problem);
+
+        for _ in 0..10 {
+            search.step();
+        }
+
+        let best = search.best().unwrap();
+        let path = search.path_to(&best.array);
+        assert_eq!(path[0], problem.start);
+        assert_eq!(path.len() as u64, best.g_result + 1);
+        assert_eq!(search.solution().status, Status::InProgress);
+    }
+
+    #[test]
+    fn search_run_budget() {
+        let problem = Problem {
+            start: vec![7, 1, 8, 5, 2, 6, 3, 4, 0],
+            end: generator::classic(3),
+            size: 3,
+            heuristic: Heuristic::Manhattan,
+            g_weight: 1,
+            h_weight: 1,
+        };
+        let mut search = Search::new(&problem);
+        let budget = Budget {
+            expanded: Some(50),
+            ..Budget::default()
+        };
+
+        assert_eq!(search.run(&mut SearchOptions::default(), &budget), Some(Limit::Expanded));
+        assert_eq!(search.expanded(), 50);
+        assert_eq!(search.solution().status, Status::OutOfBudget);
+        assert_eq!(search.run(&mut SearchOptions::default(), &Budget::default()), None);
+        assert_eq!(search.solution().status, Status::Solved);
+    }
+}
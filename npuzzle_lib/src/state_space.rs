@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use solver::neighbors;
+use types::Atom;
+use types::Puzzle;
+use types::Statistic;
+
+use util::find_empty_pos;
+#[cfg(feature = "native")]
+use util::print_puzzle;
+
+pub static TABLE_SIZE: Atom = 3;
+
+const STATES_LEN: usize = 9;
+const STATES: usize = 362880;
+const UNREACHABLE: u8 = u8::MAX;
+const MAGIC: &[u8] = b"NPDT";
+
+// Optimal distance to `goal` of every 3x3 state, indexed by permutation rank
+pub struct DistanceTable {
+    pub goal: Puzzle,
+    distances: Vec<u8>,
+}
+
+// Lehmer code of the permutation, in 0..n!
+pub fn rank(puzzle: &Puzzle) -> usize {
+    let mut rank = 0;
+
+    for i in 0..puzzle.len() {
+        let smaller = puzzle[i + 1..].iter().filter(|&&v| v < puzzle[i]).count();
+        rank = rank * (puzzle.len() - i) + smaller;
+    }
+
+    rank
+}
+
+pub fn unrank(mut rank: usize, len: usize) -> Puzzle {
+    let mut left: Vec<Atom> = (0..len as Atom).collect();
+    let mut digits = vec![0; len];
+
+    for i in (0..len).rev() {
+        let base = len - i;
+        digits[i] = rank % base;
+        rank /= base;
+    }
+
+    digits.iter().map(|&d| left.remove(d)).collect()
+}
+
+impl DistanceTable {
+    // Breadth-first enumeration of every state reachable from the goal
+    pub fn build(goal: &Puzzle) -> DistanceTable {
+        assert!(
+            goal.len() == STATES_LEN,
+            "DistanceTable: only {}x{} puzzles are supported",
+            TABLE_SIZE,
+            TABLE_SIZE
+        );
+
+        let mut distances = vec![UNREACHABLE; STATES];
+        let mut queue = VecDeque::new();
+
+        distances[rank(goal)] = 0;
+        queue.push_back((goal.clone(), find_empty_pos(goal)));
+
+        while let Some((puzzle, pos)) = queue.pop_front() {
+            let distance = distances[rank(&puzzle)];
+
+            for (neighbor, neighbor_pos) in neighbors(&puzzle, pos, TABLE_SIZE) {
+                let r = rank(&neighbor);
+
+                if distances[r] == UNREACHABLE {
+                    distances[r] = distance + 1;
+                    queue.push_back((neighbor, neighbor_pos));
+                }
+            }
+        }
+
+        DistanceTable {
+            goal: goal.clone(),
+            distances,
+        }
+    }
+
+    pub fn distance(&self, puzzle: &Puzzle) -> Option<u8> {
+        if puzzle.len() != STATES_LEN {
+            return None;
+        }
+
+        match self.distances[rank(puzzle)] {
+            UNREACHABLE => None,
+            d => Some(d),
+        }
+    }
+
+    // Number of states at each distance from the goal
+    pub fn histogram(&self) -> Vec<Statistic> {
+        let mut histogram = vec![0; self.diameter() + 1];
+
+        for &d in &self.distances {
+            if d != UNREACHABLE {
+                histogram[d as usize] += 1;
+            }
+        }
+
+        histogram
+    }
+
+    pub fn diameter(&self) -> usize {
+        self.distances
+            .iter()
+            .filter(|&&d| d != UNREACHABLE)
+            .max()
+            .map_or(0, |&d| d as usize)
+    }
+
+    pub fn hardest(&self) -> Vec<Puzzle> {
+        let diameter = self.diameter() as u8;
+
+        (0..STATES)
+            .filter(|&r| self.distances[r] == diameter)
+            .map(|r| unrank(r, STATES_LEN))
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = Vec::with_capacity(MAGIC.len() + 1 + STATES_LEN + STATES);
+
+        content.extend_from_slice(MAGIC);
+        content.push(TABLE_SIZE as u8);
+        content.extend(self.goal.iter().map(|&v| v as u8));
+        content.extend_from_slice(&self.distances);
+
+        fs::write(path, content)
+    }
+
+    pub fn load(path: &Path) -> io::Result<DistanceTable> {
+        let content = fs::read(path)?;
+        let header = MAGIC.len() + 1 + STATES_LEN;
+
+        if content.len() != header + STATES
+            || &content[..MAGIC.len()] != MAGIC
+            || content[MAGIC.len()] as Atom != TABLE_SIZE
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a 3x3 distance table",
+            ));
+        }
+
+        Ok(DistanceTable {
+            goal: content[MAGIC.len() + 1..header]
+                .iter()
+                .map(|&v| v as Atom)
+                .collect(),
+            distances: content[header..].to_vec(),
+        })
+    }
+}
+
+#[cfg(feature = "native")]
+pub fn print_report(table: &DistanceTable) {
+    let histogram = table.histogram();
+    let hardest = table.hardest();
+
+    println!("-----------------");
+    println!(" - Reachable states: {}", histogram.iter().sum::<Statistic>());
+    println!(" - Diameter: {}", table.diameter());
+    println!(" - Distance histogram:");
+    for (d, count) in histogram.iter().enumerate() {
+        println!("   {:>2}: {}", d, count);
+    }
+    println!(" - Hardest positions: {}", hardest.len());
+    println!("-----------------");
+    for p in &hardest {
+        print_puzzle(p, TABLE_SIZE);
+        println!("-----------------");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use generator;
+    use std::env;
+
+    #[test]
+    fn rank_bounds() {
+        assert_eq!(rank(&vec![0, 1, 2, 3, 4, 5, 6, 7, 8]), 0);
+        assert_eq!(rank(&vec![8, 7, 6, 5, 4, 3, 2, 1, 0]), STATES - 1);
+    }
+
+    #[test]
+    fn rank_unrank() {
+        for r in &[0, 1, 5040, 123456, STATES - 1] {
+            assert_eq!(rank(&unrank(*r, 9)), *r);
+        }
+    }
+
+    #[test]
+    fn classic_table() {
+        let table = DistanceTable::build(&generator::classic(3));
+        let histogram = table.histogram();
+
+        assert_eq!(histogram.iter().sum::<Statistic>(), STATES / 2);
+        assert_eq!(table.diameter(), 31);
+        assert_eq!(
+            table.hardest(),
+            vec![
+                vec![6, 4, 7, 8, 5, 0, 3, 2, 1],
+                vec![8, 6, 7, 2, 5, 4, 3, 0, 1],
+            ]
+        );
+        assert_eq!(table.distance(&generator::classic(3)), Some(0));
+        assert_eq!(table.distance(&vec![1, 2, 3, 4, 5, 6, 8, 7, 0]), None);
+
+        let path = env::temp_dir().join(format!("rustaquin-classic-table-{}.bin", ::std::process::id()));
+        table.save(&path).unwrap();
+        let loaded = DistanceTable::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.goal, table.goal);
+        assert_eq!(loaded.histogram(), histogram);
+    }
+}
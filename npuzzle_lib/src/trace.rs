@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use types::Atom;
+use types::Puzzle;
+use types::Result;
+use types::Statistic;
+
+use util::format_puzzle;
+
+// One node taken out of the open set and expanded by a solver
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Expansion {
+    pub order: Statistic,
+    pub state: Puzzle,
+    pub parent: Option<Puzzle>,
+    pub g_result: Result,
+    pub h_result: Result,
+    pub f_result: Result,
+}
+
+// Expansions recorded by a solver, up to `cap` of them
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trace {
+    pub cap: Statistic,
+    pub expansions: Vec<Expansion>,
+    pub truncated: bool,
+}
+
+impl Trace {
+    pub fn new(cap: Statistic) -> Trace {
+        Trace {
+            cap,
+            expansions: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    pub fn record(&mut self, expansion: Expansion) {
+        if self.expansions.len() < self.cap {
+            self.expansions.push(expansion);
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+fn join(puzzle: &Puzzle) -> String {
+    let values: Vec<String> = puzzle.iter().map(|v| v.to_string()).collect();
+
+    values.join(",")
+}
+
+// Graphviz graph of the search tree, meant for small searches
+pub fn to_dot(trace: &Trace, size: Atom) -> String {
+    let mut ids: HashMap<&Puzzle, Statistic> = HashMap::new();
+    let mut out = String::new();
+
+    out.push_str("digraph search {\n");
+    out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+    for e in &trace.expansions {
+        ids.entry(&e.state).or_insert(e.order);
+
+        let _ = writeln!(
+            out,
+            "  n{} [label=\"#{}  g={} h={} f={}\\n{}\"];",
+            e.order,
+            e.order,
+            e.g_result,
+            e.h_result,
+            e.f_result,
+            format_puzzle(&e.state, size).trim_end().replace('\n', "\\n")
+        );
+
+        if let Some(parent) = e.parent.as_ref().and_then(|p| ids.get(p)) {
+            let _ = writeln!(out, "  n{} -> n{};", parent, e.order);
+        }
+    }
+
+    out.push_str("}\n");
+
+    out
+}
+
+// One JSON object per expansion, meant for larger searches
+pub fn to_jsonl(trace: &Trace) -> String {
+    let mut out = String::new();
+
+    for e in &trace.expansions {
+        let parent = match e.parent {
+            Some(ref p) => format!("[{}]", join(p)),
+            None => "null".to_string(),
+        };
+
+        let _ = writeln!(
+            out,
+            "{{\"order\":{},\"state\":[{}],\"parent\":{},\"g\":{},\"h\":{},\"f\":{}}}",
+            e.order,
+            join(&e.state),
+            parent,
+            e.g_result,
+            e.h_result,
+            e.f_result
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expansion(order: Statistic, state: Puzzle, parent: Option<Puzzle>) -> Expansion {
+        Expansion {
+            order,
+            state,
+            parent,
+            g_result: order as Result,
+            h_result: 1,
+            f_result: order as Result + 1,
+        }
+    }
+
+    fn sample() -> Trace {
+        let mut trace = Trace::new(2);
+
+        trace.record(expansion(0, vec![1, 2, 3, 0], None));
+        trace.record(expansion(1, vec![1, 2, 0, 3], Some(vec![1, 2, 3, 0])));
+        trace.record(expansion(2, vec![1, 0, 2, 3], Some(vec![1, 2, 0, 3])));
+
+        trace
+    }
+
+    #[test]
+    fn record_cap() {
+        let trace = sample();
+
+        assert_eq!(trace.expansions.len(), 2);
+        assert!(trace.truncated);
+    }
+
+    #[test]
+    fn to_dot_edges() {
+        let dot = to_dot(&sample(), 2);
+
+        assert!(dot.starts_with("digraph search {"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("#1  g=1 h=1 f=2\\n1 2\\n0 3"));
+    }
+
+    #[test]
+    fn to_jsonl_lines() {
+        let jsonl = to_jsonl(&sample());
+        let lines: Vec<&str> = jsonl.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "{\"order\":0,\"state\":[1,2,3,0],\"parent\":null,\"g\":0,\"h\":1,\"f\":1}"
+        );
+    }
+}
@@ -1,5 +1,14 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::time::SystemTime;
+use std::time::Duration;
+
+use cancel::CancelToken;
+use clock;
+use clock::Clock;
+#[cfg(feature = "serde")]
+use heuristics;
+use observer::Reporter;
+use trace::Trace;
 
 // Types
 pub type Atom = u16;
@@ -9,14 +18,23 @@ pub type Result = u64;
 pub type Puzzle = Vec<Atom>;
 pub type AtomPair = (Atom, Atom);
 
-pub type Heuristic = fn(a: &Puzzle, b: &Puzzle, size: Atom) -> Result;
-pub type Solver = fn(p: &Problem) -> Solution;
+// Heuristics are told apart by name rather than by function pointer, which
+// Rust does not promise to be unique
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heuristic {
+    Conflicts,
+    Hamming,
+    Manhattan,
+}
+
+pub type Solver = for<'a> fn(p: &'a Problem, o: &mut SearchOptions) -> Solution<'a>;
 
 // Constants
 pub static MAX_PUZZLE_SIZE: Atom = 100;
 pub static MAX_ARRAY_SIZE: Atom = MAX_PUZZLE_SIZE * MAX_PUZZLE_SIZE;
 
-#[derive(Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Node {
     pub array: Puzzle,
     pub pos: Atom,
@@ -25,14 +43,30 @@ pub struct Node {
     pub f_result: Result,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Status {
+    Solved,
+    Unsolvable,
+    Cancelled,
+    // stopped on a limit of its budget, before finding the goal
+    OutOfBudget,
+    // stepped by hand and not finished yet
+    InProgress,
+}
+
+// The problem is borrowed from the caller of a solver, and owned once read
+// back from a serialized solution
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Solution<'a> {
-    pub problem: &'a Problem,
+    pub problem: Cow<'a, Problem>,
+    pub status: Status,
     pub path: Vec<Puzzle>,
     pub max_states: Statistic,
     pub opened_states: Statistic,
     pub current_open_states: Statistic,
     pub closed_states: Statistic,
-    pub time: SystemTime,
+    pub time: Duration,
 }
 
 impl Ord for Node {
@@ -50,16 +84,126 @@ impl PartialOrd for Node {
     }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Problem {
     pub start: Puzzle,
     pub end: Puzzle,
     pub size: Atom,
+    #[cfg_attr(feature = "serde", serde(with = "heuristics::by_name"))]
     pub heuristic: Heuristic,
     pub g_weight: Result,
     pub h_weight: Result,
 }
 
+// Optional instrumentation of a solver run
+pub struct SearchOptions {
+    pub trace: Option<Trace>,
+    pub reporter: Option<Reporter>,
+    pub cancel: Option<CancelToken>,
+    pub clock: Clock,
+}
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions {
+            trace: None,
+            reporter: None,
+            cancel: None,
+            clock: clock::DEFAULT,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.is_cancelled())
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ParsedPuzzle {
     pub container: Puzzle,
     pub size: Atom,
 }
+
+// A start, its goal if given and `key: value` metadata, read from one file
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LabeledPuzzle {
+    pub start: ParsedPuzzle,
+    pub goal: Option<ParsedPuzzle>,
+    pub metadata: Vec<(String, String)>,
+}
+
+impl LabeledPuzzle {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|m| m.0 == key).map(|m| &m.1 as &str)
+    }
+
+    // Length of an optimal solution, when the file gives it
+    pub fn expected_length(&self) -> Option<usize> {
+        self.get("length").and_then(|l| l.parse().ok())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use generator;
+    use heuristics;
+    use solver;
+
+    fn problem() -> Problem {
+        Problem {
+            start: vec![1, 2, 3, 4, 5, 6, 0, 7, 8],
+            end: generator::classic(3),
+            size: 3,
+            heuristic: Heuristic::Conflicts,
+            g_weight: 1,
+            h_weight: 2,
+        }
+    }
+
+    fn assert_same(a: &Solution, b: &Solution) {
+        assert_eq!(a.problem.start, b.problem.start);
+        assert_eq!(a.problem.end, b.problem.end);
+        assert_eq!(a.problem.size, b.problem.size);
+        assert_eq!(heuristics::name_of(b.problem.heuristic), Some("conflicts"));
+        assert_eq!((a.problem.g_weight, a.problem.h_weight), (b.problem.g_weight, b.problem.h_weight));
+        assert_eq!(a.status, b.status);
+        assert_eq!(a.path, b.path);
+        assert_eq!(a.max_states, b.max_states);
+        assert_eq!(a.opened_states, b.opened_states);
+        assert_eq!(a.current_open_states, b.current_open_states);
+        assert_eq!(a.closed_states, b.closed_states);
+        assert_eq!(a.time, b.time);
+    }
+
+    #[test]
+    fn solution_json_round_trip() {
+        let problem = problem();
+        let solution = solver::astar(&problem);
+        let json = ::serde_json::to_string(&solution).unwrap();
+
+        assert!(json.contains("\"heuristic\":\"conflicts\""));
+        assert!(json.contains("\"status\":\"Solved\""));
+        assert_same(&solution, &::serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn solution_binary_round_trip() {
+        let problem = problem();
+        let solution = solver::astar(&problem);
+        let bytes = ::rmp_serde::to_vec(&solution).unwrap();
+
+        assert_same(&solution, &::rmp_serde::from_slice(&bytes).unwrap());
+    }
+
+    #[test]
+    fn unknown_heuristic() {
+        let json = ::serde_json::to_string(&problem()).unwrap().replace("\"conflicts\"", "\"euclid\"");
+        let error = ::serde_json::from_str::<Problem>(&json).err().unwrap();
+
+        assert!(error.to_string().contains("unknown heuristic euclid"));
+    }
+}
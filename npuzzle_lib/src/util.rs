@@ -32,15 +32,28 @@ pub fn xy(value: Atom, size: Atom) -> AtomPair {
     (value % size, value / size)
 }
 
-#[inline]
-pub fn print_puzzle(puzzle: &Puzzle, size: Atom) {
+// Rows of right aligned values, as wide as the largest one
+pub fn format_puzzle(puzzle: &Puzzle, size: Atom) -> String {
+    let width = puzzle.iter().max().map_or(1, |v| v.to_string().len());
+    let mut s = String::new();
+
     for i in 0..puzzle.len() {
-        print!("{} ", puzzle[i]);
+        s.push_str(&format!("{:>1$}", puzzle[i], width));
 
         if i as Atom % size == size - 1 {
-            println!("");
+            s.push('\n');
+        } else {
+            s.push(' ');
         }
     }
+
+    s
+}
+
+#[cfg(feature = "native")]
+#[inline]
+pub fn print_puzzle(puzzle: &Puzzle, size: Atom) {
+    print!("{}", format_puzzle(puzzle, size));
 }
 
 #[cfg(test)]
@@ -64,6 +77,14 @@ mod tests {
         super::xy(1234, 3);
     }
 
+    #[test]
+    fn format_puzzle() {
+        let v: Vec<Atom> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0];
+        let expected = " 1  2  3  4\n 5  6  7  8\n 9 10 11 12\n13 14 15  0\n";
+
+        assert_eq!(super::format_puzzle(&v, 4), expected);
+    }
+
     #[test]
     fn find_empty_pos() {
         let v: Vec<Atom> = vec![1, 2, 0, 3];
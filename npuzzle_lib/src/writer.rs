@@ -0,0 +1,163 @@
+use types::ParsedPuzzle;
+
+use util::format_puzzle;
+
+// What is known about where a puzzle comes from, written as comments above it
+#[derive(Clone, Default)]
+pub struct Header {
+    pub generator: Option<String>,
+    pub seed: Option<u64>,
+    pub optimal_length: Option<usize>,
+}
+
+// A comment may not span several lines, or the rest would be read as tiles.
+// Neither may it hold a `#`, which would end a metadata value.
+fn one_line(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| if c == '\n' || c == '\r' || c == '#' { ' ' } else { c })
+        .collect();
+
+    value.trim().to_string()
+}
+
+fn comment(s: &mut String, key: &str, value: &str) {
+    s.push_str(&format!("# {}: {}\n", key, one_line(value)));
+}
+
+// Writes a puzzle in the format read by input_parser: the header as comments,
+// the size, then one row per line with right aligned columns
+pub fn to_string(puzzle: &ParsedPuzzle, header: &Header) -> String {
+    let mut s = String::new();
+
+    if let Some(ref generator) = header.generator {
+        comment(&mut s, "generator", generator);
+    }
+    if let Some(seed) = header.seed {
+        comment(&mut s, "seed", &seed.to_string());
+    }
+    if let Some(length) = header.optimal_length {
+        comment(&mut s, "optimal length", &length.to_string());
+    }
+    s.push_str(&format!("{}\n", puzzle.size));
+    s.push_str(&format_puzzle(&puzzle.container, puzzle.size));
+
+    s
+}
+
+// Writes a start and its goal as labeled sections, the header as metadata
+// that input_parser::parse_labeled gives back
+pub fn to_labeled_string(start: &ParsedPuzzle, goal: &ParsedPuzzle, header: &Header) -> String {
+    let mut s = String::new();
+
+    if let Some(ref generator) = header.generator {
+        s.push_str(&format!("generator: {}\n", one_line(generator)));
+    }
+    if let Some(seed) = header.seed {
+        s.push_str(&format!("seed: {}\n", seed));
+    }
+    if let Some(length) = header.optimal_length {
+        s.push_str(&format!("length: {}\n", length));
+    }
+    s.push_str("\nstart:\n");
+    s.push_str(&to_string(start, &Header::default()));
+    s.push_str("\ngoal:\n");
+    s.push_str(&to_string(goal, &Header::default()));
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator;
+    use input_parser;
+
+    fn puzzle(size: u16) -> ParsedPuzzle {
+        let goal = ParsedPuzzle {
+            container: generator::snail(size),
+            size,
+        };
+
+        generator::generate_seeded_puzzle(&goal, 1000, 7)
+    }
+
+    #[test]
+    fn aligned_columns() {
+        let puzzle = ParsedPuzzle {
+            container: generator::snail(4),
+            size: 4,
+        };
+
+        assert_eq!(
+            to_string(&puzzle, &Header::default()),
+            "4\n 1  2  3  4\n12 13 14  5\n11  0 15  6\n10  9  8  7\n"
+        );
+    }
+
+    #[test]
+    fn header_comments() {
+        let header = Header {
+            generator: Some("random walk\nof 1000 moves".to_string()),
+            seed: Some(7),
+            optimal_length: Some(12),
+        };
+        let s = to_string(&puzzle(3), &header);
+
+        assert!(s.starts_with("# generator: random walk of 1000 moves\n# seed: 7\n# optimal length: 12\n3\n"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let header = Header {
+            generator: Some("test".to_string()),
+            seed: Some(7),
+            optimal_length: None,
+        };
+
+        for size in 3..12 {
+            let puzzle = puzzle(size);
+            let parsed = input_parser::parse_str(&to_string(&puzzle, &header)).unwrap();
+
+            assert_eq!(parsed.size, puzzle.size);
+            assert_eq!(parsed.container, puzzle.container);
+        }
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn round_trip_file() {
+        use std::env;
+        use std::fs;
+
+        let puzzle = puzzle(5);
+        let file = env::temp_dir().join(format!("npuzzle_writer_{}.txt", ::std::process::id()));
+
+        fs::write(&file, to_string(&puzzle, &Header::default())).unwrap();
+        let parsed = input_parser::parse(Some(&file.to_string_lossy().into_owned())).unwrap();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(parsed.size, puzzle.size);
+        assert_eq!(parsed.container, puzzle.container);
+    }
+
+    #[test]
+    fn labeled_round_trip() {
+        let goal = ParsedPuzzle {
+            container: generator::classic(4),
+            size: 4,
+        };
+        let header = Header {
+            generator: Some("test # 1".to_string()),
+            seed: Some(7),
+            optimal_length: Some(30),
+        };
+        let labeled = input_parser::parse_labeled(&to_labeled_string(&puzzle(4), &goal, &header)).unwrap();
+
+        assert_eq!(labeled.start.container, puzzle(4).container);
+        assert_eq!(labeled.goal.as_ref().unwrap().container, goal.container);
+        assert_eq!(labeled.get("generator"), Some("test   1"));
+        assert_eq!(labeled.get("seed"), Some("7"));
+        assert_eq!(labeled.expected_length(), Some(30));
+    }
+}
@@ -0,0 +1,236 @@
+// Parses every puzzle of test_puzzle/ and checks it against what corpus.txt
+// records about it
+#![cfg(feature = "native")]
+
+extern crate npuzzle_lib;
+
+use std::fs;
+use std::path::PathBuf;
+
+use npuzzle_lib::checker;
+use npuzzle_lib::diagnostics;
+use npuzzle_lib::generator;
+use npuzzle_lib::heuristics;
+use npuzzle_lib::input_parser;
+use npuzzle_lib::solver;
+use npuzzle_lib::types::ParsedPuzzle;
+use npuzzle_lib::types::Heuristic;
+use npuzzle_lib::types::Problem;
+use npuzzle_lib::types::Status;
+
+static ERROR_KINDS: [(&str, &str); 10] = [
+    ("ERR_DUPLICATED_VALUE", input_parser::ERR_DUPLICATED_VALUE),
+    ("ERR_INVALID_ELMT", input_parser::ERR_INVALID_ELMT),
+    ("ERR_MISSING_VALUES", diagnostics::ERR_MISSING_VALUES),
+    ("ERR_NUMBER_INVALID", input_parser::ERR_NUMBER_INVALID),
+    ("ERR_NUMBER_OF_LINES", input_parser::ERR_NUMBER_OF_LINES),
+    ("ERR_NUMBER_PER_LINE", input_parser::ERR_NUMBER_PER_LINE),
+    ("ERR_SIZE", input_parser::ERR_SIZE),
+    ("ERR_SIZE_INVALID", input_parser::ERR_SIZE_INVALID),
+    ("ERR_SIZE_MISSING", diagnostics::ERR_SIZE_MISSING),
+    ("ERR_SIZE_SYNTAX", input_parser::ERR_SIZE_SYNTAX),
+];
+
+// One block of corpus.txt, its keys in the order they were written
+struct Entry {
+    fields: Vec<(String, String)>,
+}
+
+impl Entry {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|f| f.0 == key).map(|f| f.1.as_str())
+    }
+
+    fn file(&self) -> &str {
+        self.get("file").unwrap()
+    }
+}
+
+fn corpus_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..").join("test_puzzle")
+}
+
+fn entries() -> Vec<Entry> {
+    let content = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("corpus.txt")).unwrap();
+    let mut entries = Vec::new();
+
+    for block in content.split("\n\n") {
+        let fields: Vec<(String, String)> = block
+            .lines()
+            .filter(|l| !l.starts_with('#'))
+            .map(|l| {
+                let mut kv = l.splitn(2, ':');
+                let key = kv.next().unwrap().trim().to_string();
+                let value = kv.next().expect(l).trim().to_string();
+
+                (key, value)
+            })
+            .collect();
+
+        if !fields.is_empty() {
+            assert_eq!(fields[0].0, "file", "every entry starts with its file");
+            entries.push(Entry { fields });
+        }
+    }
+
+    entries
+}
+
+// Some messages start with another one, "Invalid number of lines" with
+// "Invalid number": the longest that matches is the kind
+fn kind_of(message: &str) -> &'static str {
+    ERROR_KINDS
+        .iter()
+        .filter(|k| message == k.1 || message.starts_with(&format!("{} ", k.1)))
+        .max_by_key(|k| k.1.len())
+        .map(|k| k.0)
+        .unwrap_or_else(|| panic!("unknown error kind: {}", message))
+}
+
+fn goal(entry: &Entry, size: u16) -> Vec<u16> {
+    match entry.get("goal") {
+        Some("snail") => generator::snail(size),
+        Some("classic") => generator::classic(size),
+        g => panic!("{}: unknown goal {:?}", entry.file(), g),
+    }
+}
+
+fn parse(entry: &Entry) -> (String, Option<ParsedPuzzle>) {
+    let path = corpus_dir().join(entry.file());
+    let content = fs::read_to_string(&path).unwrap();
+
+    (content, input_parser::parse(Some(&path.to_string_lossy().into_owned())))
+}
+
+fn optimal_length(entry: &Entry, puzzle: &ParsedPuzzle) -> usize {
+    let problem = Problem {
+        start: puzzle.container.clone(),
+        end: goal(entry, puzzle.size),
+        size: puzzle.size,
+        heuristic: Heuristic::Conflicts,
+        g_weight: 1,
+        h_weight: 1,
+    };
+    let solution = solver::astar(&problem);
+
+    assert_eq!(solution.status, Status::Solved, "{}", entry.file());
+    solution.path.len() - 1
+}
+
+// Distance of the blank to its cell in the goal, which every move changes by one
+fn blank_distance(start: &[u16], goal: &[u16], size: u16) -> usize {
+    let size = size as usize;
+    let from = start.iter().position(|&t| t == 0).unwrap();
+    let to = goal.iter().position(|&t| t == 0).unwrap();
+
+    (from / size).abs_diff(to / size) + (from % size).abs_diff(to % size)
+}
+
+fn check_lengths(slow: bool) {
+    for entry in entries() {
+        let expected = match entry.get("length") {
+            Some(length) => length.parse::<usize>().unwrap(),
+            None => continue,
+        };
+        if entry.get("slow").is_some() != slow {
+            continue;
+        }
+        let puzzle = parse(&entry).1.unwrap();
+
+        assert_eq!(optimal_length(&entry, &puzzle), expected, "{}", entry.file());
+    }
+}
+
+#[test]
+fn corpus_covers_every_file() {
+    let mut files: Vec<String> = fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    let mut recorded: Vec<String> = entries().iter().map(|e| e.file().to_string()).collect();
+
+    files.sort();
+    recorded.sort();
+    assert_eq!(files, recorded);
+}
+
+#[test]
+fn corpus_validity_and_errors() {
+    for entry in entries() {
+        let (content, parsed) = parse(&entry);
+        let kinds: Vec<&str> = diagnostics::check(&content).iter().map(|d| kind_of(&d.message)).collect();
+
+        match entry.get("errors") {
+            Some(errors) => {
+                let expected: Vec<&str> = errors.split(',').map(|e| e.trim()).collect();
+
+                assert!(parsed.is_none(), "{} should not parse", entry.file());
+                assert!(input_parser::parse_str(&content).is_err(), "{}", entry.file());
+                assert_eq!(kinds, expected, "{}", entry.file());
+            }
+            None => {
+                assert!(parsed.is_some(), "{} should parse", entry.file());
+                assert!(kinds.is_empty(), "{}: {:?}", entry.file(), kinds);
+            }
+        }
+    }
+}
+
+#[test]
+fn corpus_solvability() {
+    for entry in entries().iter().filter(|e| e.get("errors").is_none()) {
+        let puzzle = parse(entry).1.unwrap();
+        let expected = match entry.get("solvable") {
+            Some("yes") => true,
+            Some("no") => false,
+            s => panic!("{}: solvable should be yes or no, not {:?}", entry.file(), s),
+        };
+
+        assert_eq!(
+            checker::is_solvable(&puzzle.container, &goal(entry, puzzle.size), puzzle.size),
+            expected,
+            "{}",
+            entry.file()
+        );
+    }
+}
+
+#[test]
+fn corpus_optimal_lengths() {
+    check_lengths(false);
+}
+
+// Cheap checks of every recorded length, slow ones included: it is no shorter
+// than the heuristic, which never overestimates, no longer than what a weighted
+// search finds, and has the parity of the distance of the blank to its goal
+#[test]
+fn corpus_length_bounds() {
+    for entry in entries() {
+        let expected = match entry.get("length") {
+            Some(length) => length.parse::<usize>().unwrap(),
+            None => continue,
+        };
+        let puzzle = parse(&entry).1.unwrap();
+        let end = goal(&entry, puzzle.size);
+        let analysis = checker::analyze(&puzzle.container, &end, puzzle.size);
+
+        assert!(
+            heuristics::linear_conflicts(&puzzle.container, &end, puzzle.size) as usize <= expected,
+            "{}",
+            entry.file()
+        );
+        assert!(analysis.upper_bound.is_none_or(|b| expected <= b), "{}", entry.file());
+        assert_eq!(
+            expected % 2,
+            blank_distance(&puzzle.container, &end, puzzle.size) % 2,
+            "{}",
+            entry.file()
+        );
+    }
+}
+
+#[test]
+#[ignore]
+fn corpus_slow_optimal_lengths() {
+    check_lengths(true);
+}
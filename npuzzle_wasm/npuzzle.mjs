@@ -0,0 +1,82 @@
+// JS side of npuzzle_wasm. Works in browsers and in node:
+//
+//   const npuzzle = await load(fetch('npuzzle_wasm.wasm'));
+//   npuzzle.solve([1, 2, 3, 8, 0, 4, 7, 6, 5], 3, { timeoutMs: 500 });
+
+const GOALS = { snail: 0, classic: 1 };
+const HEURISTICS = { conflicts: 0, hamming: 1, manhattan: 2 };
+
+function option(table, name, kind) {
+  if (!(name in table)) {
+    throw new Error(`${kind} ${name} is not valid`);
+  }
+  return table[name];
+}
+
+// `source` is a Response, a promise of one, or the bytes of the module
+export async function load(source) {
+  const imports = { env: { npuzzle_now_ms: () => performance.now() } };
+  const resolved = await source;
+  const { instance } =
+    typeof Response !== 'undefined' && resolved instanceof Response
+      ? await WebAssembly.instantiateStreaming(resolved, imports)
+      : await WebAssembly.instantiate(resolved, imports);
+  const wasm = instance.exports;
+  const encoder = new TextEncoder();
+  const decoder = new TextDecoder();
+
+  // Result buffers are a little endian u32 length followed by JSON text
+  function result(ptr) {
+    try {
+      const length = new DataView(wasm.memory.buffer).getUint32(ptr, true);
+      const json = JSON.parse(decoder.decode(new Uint8Array(wasm.memory.buffer, ptr + 4, length)));
+      if (json.error !== undefined) {
+        throw new Error(json.error);
+      }
+      return json;
+    } finally {
+      wasm.npuzzle_result_free(ptr);
+    }
+  }
+
+  function withText(text, call) {
+    const bytes = encoder.encode(text);
+    const ptr = wasm.npuzzle_alloc(bytes.length);
+    try {
+      new Uint8Array(wasm.memory.buffer, ptr, bytes.length).set(bytes);
+      return result(call(ptr, bytes.length));
+    } finally {
+      wasm.npuzzle_dealloc(ptr, bytes.length);
+    }
+  }
+
+  const compact = (tiles, size) => `${size} ${tiles.join(' ')}`;
+
+  return {
+    // { status, expanded, closed_states, max_states, time_ms, moves, length }
+    // where status is solved, unsolvable or out_of_budget
+    solve(tiles, size, { goal = 'snail', heuristic = 'conflicts', maxExpanded = 0, timeoutMs = 0 } = {}) {
+      const g = option(GOALS, goal, 'goal');
+      const h = option(HEURISTICS, heuristic, 'heuristic');
+      return withText(compact(tiles, size), (ptr, len) =>
+        wasm.npuzzle_solve(ptr, len, g, h, maxExpanded, timeoutMs));
+    },
+
+    // Same seed, same puzzle. Defaults to a random seed from Math.random.
+    generate(size, { goal = 'snail', iterations = 10000, seed = Math.floor(Math.random() * 2 ** 32) } = {}) {
+      const g = option(GOALS, goal, 'goal');
+      return result(wasm.npuzzle_generate(size, g, iterations, seed)).tiles;
+    },
+
+    isSolvable(tiles, size, { goal = 'snail' } = {}) {
+      const g = option(GOALS, goal, 'goal');
+      return withText(compact(tiles, size), (ptr, len) =>
+        wasm.npuzzle_is_solvable(ptr, len, g)).solvable;
+    },
+
+    // A puzzle in the 42 file format, as { size, tiles }
+    parse(text) {
+      return withText(text, (ptr, len) => wasm.npuzzle_parse(ptr, len));
+    },
+  };
+}
@@ -0,0 +1,69 @@
+// Headless checks of the wasm build, run with node:
+//
+//   cargo build --release --target wasm32-unknown-unknown
+//   node run_tests.mjs [path/to/npuzzle_wasm.wasm]
+
+import { readFile } from 'node:fs/promises';
+import { load } from './npuzzle.mjs';
+
+const path = process.argv[2] ??
+  new URL('./target/wasm32-unknown-unknown/release/npuzzle_wasm.wasm', import.meta.url);
+const npuzzle = await load(await readFile(path));
+let failed = 0;
+
+function check(name, test) {
+  try {
+    test();
+    console.log(`ok   ${name}`);
+  } catch (e) {
+    failed += 1;
+    console.log(`FAIL ${name}: ${e.message}`);
+  }
+}
+
+function assert(condition, message) {
+  if (!condition) {
+    throw new Error(message);
+  }
+}
+
+check('solve', () => {
+  const r = npuzzle.solve([7, 1, 8, 5, 2, 6, 3, 4, 0], 3, { goal: 'classic', heuristic: 'manhattan' });
+  assert(r.status === 'solved', `status ${r.status}`);
+  assert(r.length === r.moves.length && r.length >= 24, `length ${r.length}`);
+});
+
+check('solve within budget', () => {
+  const r = npuzzle.solve([0, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1], 4, { maxExpanded: 100 });
+  assert(r.status === 'out_of_budget', `status ${r.status}`);
+  assert(r.expanded === 100, `expanded ${r.expanded}`);
+});
+
+check('solve unsolvable', () => {
+  const r = npuzzle.solve([1, 2, 3, 4, 5, 6, 8, 7, 0], 3, { goal: 'classic' });
+  assert(r.status === 'unsolvable', `status ${r.status}`);
+});
+
+check('generate', () => {
+  const a = npuzzle.generate(4, { seed: 7, iterations: 200 });
+  const b = npuzzle.generate(4, { seed: 7, iterations: 200 });
+  assert(a.length === 16 && a.join() === b.join(), 'seeded puzzles differ');
+  assert(npuzzle.isSolvable(a, 4), 'generated puzzle not solvable');
+});
+
+check('parse', () => {
+  const p = npuzzle.parse('# start\n3\n1 2 3\n8 0 4\n7 6 5\n');
+  assert(p.size === 3 && p.tiles.join() === '1,2,3,8,0,4,7,6,5', JSON.stringify(p));
+});
+
+check('errors', () => {
+  let message = null;
+  try {
+    npuzzle.solve([1, 1, 2], 3);
+  } catch (e) {
+    message = e.message;
+  }
+  assert(message !== null, 'invalid puzzle accepted');
+});
+
+process.exitCode = failed > 0 ? 1 : 0;
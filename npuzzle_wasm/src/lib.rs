@@ -0,0 +1,334 @@
+// Browser build of npuzzle_lib, for wasm32-unknown-unknown. Strings go in as
+// UTF-8 bytes written in memory obtained from npuzzle_alloc, and results come
+// back as a pointer to a little endian u32 length followed by JSON text, to be
+// released with npuzzle_result_free. npuzzle.mjs hides all of this.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::fmt::Write;
+use std::ptr;
+use std::slice;
+use std::str;
+use std::time::Duration;
+
+use npuzzle_lib::*;
+use solver::Budget;
+use solver::Search;
+use types::Atom;
+use types::ParsedPuzzle;
+use types::Puzzle;
+use types::SearchOptions;
+use types::Status;
+
+const GOAL_SNAIL: i32 = 0;
+const GOAL_CLASSIC: i32 = 1;
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    // provided by the page, usually performance.now()
+    fn npuzzle_now_ms() -> f64;
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> Duration {
+    Duration::from_secs_f64(unsafe { npuzzle_now_ms() } / 1000.0)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use npuzzle_lib::clock::stopped as now;
+
+fn join(puzzle: &[Atom]) -> String {
+    let values: Vec<String> = puzzle.iter().map(|v| v.to_string()).collect();
+
+    values.join(",")
+}
+
+// Messages can echo parts of the input, so anything JSON does not allow in a
+// string as is gets escaped
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c < ' ' || c == '\u{7f}' => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn error(message: &str) -> String {
+    format!("{{\"error\":\"{}\"}}", escape(message))
+}
+
+fn goal(goal: i32, size: Atom) -> Option<Puzzle> {
+    match goal {
+        GOAL_SNAIL => Some(generator::snail(size)),
+        GOAL_CLASSIC => Some(generator::classic(size)),
+        _ => None,
+    }
+}
+
+// `input` is a compact puzzle, the size followed by the tiles
+fn read(input: &str, goal_type: i32) -> Result<(ParsedPuzzle, Puzzle), String> {
+    let puzzle = match input_parser::parse_compact(input) {
+        Ok(Some(p)) => p,
+        Ok(None) => return Err("no puzzle given".to_string()),
+        Err(e) => return Err(e),
+    };
+
+    match goal(goal_type, puzzle.size) {
+        Some(g) => Ok((puzzle, g)),
+        None => Err(format!("goal {} is not valid", goal_type)),
+    }
+}
+
+pub fn solve(input: &str, goal_type: i32, heuristic: i32, max_expanded: u32, timeout_ms: u32) -> String {
+    let (puzzle, end) = match read(input, goal_type) {
+        Ok(r) => r,
+        Err(e) => return error(&e),
+    };
+    let heuristic = match heuristics::HEURISTICS.get(heuristic as usize) {
+        Some(h) if heuristic >= 0 => h.1,
+        _ => return error(&format!("heuristic {} is not valid", heuristic)),
+    };
+
+    if !checker::is_solvable(&puzzle.container, &end, puzzle.size) {
+        return "{\"status\":\"unsolvable\"}".to_string();
+    }
+
+    let problem = types::Problem {
+        start: puzzle.container,
+        end,
+        size: puzzle.size,
+        heuristic,
+        g_weight: 1,
+        h_weight: 1,
+    };
+    let budget = Budget {
+        expanded: if max_expanded > 0 { Some(max_expanded as usize) } else { None },
+        time: if timeout_ms > 0 { Some(Duration::from_millis(timeout_ms as u64)) } else { None },
+    };
+    let mut options = SearchOptions {
+        clock: now,
+        ..SearchOptions::default()
+    };
+
+    let mut search = Search::with_clock(&problem, now);
+    search.run(&mut options, &budget);
+    let solution = search.solution();
+
+    let status = match solution.status {
+        Status::Solved => "solved",
+        Status::Unsolvable => "unsolvable",
+        Status::Cancelled => "cancelled",
+        Status::OutOfBudget => "out_of_budget",
+        Status::InProgress => "in_progress",
+    };
+    let mut out = format!(
+        "{{\"status\":\"{}\",\"expanded\":{},\"closed_states\":{},\"max_states\":{},\"time_ms\":{}",
+        status,
+        search.expanded(),
+        solution.closed_states,
+        solution.max_states,
+        solution.time.as_millis()
+    );
+
+    if solution.status == Status::Solved {
+        let moves = game::to_moves(&solution.path, problem.size);
+        let _ = write!(out, ",\"moves\":\"{}\",\"length\":{}", moves, moves.len());
+    }
+    out.push('}');
+
+    out
+}
+
+// No OS entropy in the browser: the page hands over a seed
+pub fn generate(size: u32, goal_type: i32, iterations: u32, seed: f64) -> String {
+    if size < 3 || size > types::MAX_PUZZLE_SIZE as u32 {
+        return error(&format!("size {} is not valid", size));
+    }
+
+    let size = size as Atom;
+    let goal = match goal(goal_type, size) {
+        Some(g) => ParsedPuzzle { container: g, size },
+        None => return error(&format!("goal {} is not valid", goal_type)),
+    };
+    let puzzle = generator::generate_seeded_puzzle(&goal, iterations as u64, seed as u64);
+
+    format!("{{\"size\":{},\"tiles\":[{}]}}", size, join(&puzzle.container))
+}
+
+pub fn is_solvable(input: &str, goal_type: i32) -> String {
+    match read(input, goal_type) {
+        Ok((puzzle, end)) => format!(
+            "{{\"solvable\":{}}}",
+            checker::is_solvable(&puzzle.container, &end, puzzle.size)
+        ),
+        Err(e) => error(&e),
+    }
+}
+
+// A puzzle in the 42 file format, or any other format input_parser detects
+pub fn parse(content: &str) -> String {
+    match input_parser::parse_any(content) {
+        Ok(p) => format!("{{\"size\":{},\"tiles\":[{}]}}", p.size, join(&p.container)),
+        Err(e) => error(&e),
+    }
+}
+
+// Memory handed to JS
+
+#[no_mangle]
+pub extern "C" fn npuzzle_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_dealloc(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+fn into_result(json: String) -> *mut u8 {
+    let mut bytes = (json.len() as u32).to_le_bytes().to_vec();
+
+    bytes.extend_from_slice(json.as_bytes());
+    Box::into_raw(bytes.into_boxed_slice()) as *mut u8
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_result_free(ptr: *mut u8) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let mut len = [0u8; 4];
+    len.copy_from_slice(slice::from_raw_parts(ptr, 4));
+    npuzzle_dealloc(ptr, u32::from_le_bytes(len) as usize + 4);
+}
+
+unsafe fn text<'a>(ptr: *const u8, len: usize) -> Result<&'a str, *mut u8> {
+    if ptr.is_null() {
+        return Err(into_result(error("no input given")));
+    }
+
+    str::from_utf8(slice::from_raw_parts(ptr, len)).map_err(|_| into_result(error("input is not UTF-8")))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_solve(
+    ptr: *const u8,
+    len: usize,
+    goal_type: i32,
+    heuristic: i32,
+    max_expanded: u32,
+    timeout_ms: u32,
+) -> *mut u8 {
+    match text(ptr, len) {
+        Ok(input) => into_result(solve(input, goal_type, heuristic, max_expanded, timeout_ms)),
+        Err(e) => e,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn npuzzle_generate(size: u32, goal_type: i32, iterations: u32, seed: f64) -> *mut u8 {
+    into_result(generate(size, goal_type, iterations, seed))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_is_solvable(ptr: *const u8, len: usize, goal_type: i32) -> *mut u8 {
+    match text(ptr, len) {
+        Ok(input) => into_result(is_solvable(input, goal_type)),
+        Err(e) => e,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn npuzzle_parse(ptr: *const u8, len: usize) -> *mut u8 {
+    match text(ptr, len) {
+        Ok(input) => into_result(parse(input)),
+        Err(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_classic() {
+        let json = solve("3 1 2 3 4 5 6 0 7 8", GOAL_CLASSIC, 2, 0, 0);
+
+        assert!(json.starts_with("{\"status\":\"solved\""));
+        assert!(json.ends_with(",\"moves\":\"LL\",\"length\":2}"));
+    }
+
+    #[test]
+    fn solve_budget() {
+        let json = solve("3 7 1 8 5 2 6 3 4 0", GOAL_CLASSIC, 0, 10, 0);
+
+        assert!(json.starts_with("{\"status\":\"out_of_budget\",\"expanded\":10"));
+    }
+
+    #[test]
+    fn solve_errors() {
+        assert_eq!(solve("3 1 2", GOAL_SNAIL, 0, 0, 0), error(input_parser::ERR_NUMBER_OF_LINES));
+        assert!(solve("3 1 2 3 4 5 6 0 7 8", 7, 0, 0, 0).starts_with("{\"error\""));
+        assert!(solve("3 1 2 3 4 5 6 0 7 8", GOAL_SNAIL, 9, 0, 0).starts_with("{\"error\""));
+    }
+
+    #[test]
+    fn error_escaped() {
+        assert_eq!(error("a \"b\" \\ c"), "{\"error\":\"a \\\"b\\\" \\\\ c\"}");
+        assert_eq!(error("tab\there\nnul\u{0}"), "{\"error\":\"tab\\u0009here\\u000anul\\u0000\"}");
+        assert_eq!(error("réponse"), "{\"error\":\"réponse\"}");
+        assert!(!parse("3\n1 2 \u{1}\n").contains('\u{1}'));
+    }
+
+    #[test]
+    fn generate_seeded() {
+        let json = generate(3, GOAL_SNAIL, 100, 42.0);
+
+        assert_eq!(json, generate(3, GOAL_SNAIL, 100, 42.0));
+        assert!(json.starts_with("{\"size\":3,\"tiles\":["));
+        assert!(generate(65535, GOAL_SNAIL, 1, 0.0).starts_with("{\"error\""));
+    }
+
+    #[test]
+    fn result_round_trip() {
+        let input = "3 1 2 3 8 0 4 7 6 5";
+
+        unsafe {
+            let ptr = npuzzle_alloc(input.len());
+            slice::from_raw_parts_mut(ptr, input.len()).copy_from_slice(input.as_bytes());
+
+            let result = npuzzle_is_solvable(ptr, input.len(), GOAL_SNAIL);
+            let len = u32::from_le_bytes([*result, *result.add(1), *result.add(2), *result.add(3)]);
+
+            assert_eq!(
+                slice::from_raw_parts(result.add(4), len as usize),
+                b"{\"solvable\":true}"
+            );
+            npuzzle_result_free(result);
+            npuzzle_dealloc(ptr, input.len());
+        }
+    }
+
+    #[test]
+    fn parse_42_format() {
+        assert_eq!(parse("3\n1 2 3\n8 0 4\n7 6 5\n"), "{\"size\":3,\"tiles\":[1,2,3,8,0,4,7,6,5]}");
+        assert!(parse("3\n1 2 3\n").starts_with("{\"error\""));
+        assert_eq!(parse("3:1,2,3,8,0,4,7,6,5"), "{\"size\":3,\"tiles\":[1,2,3,8,0,4,7,6,5]}");
+        assert_eq!(
+            parse("{\"size\": 3, \"tiles\": [1, 2, 3, 8, 0, 4, 7, 6, 5]}"),
+            "{\"size\":3,\"tiles\":[1,2,3,8,0,4,7,6,5]}"
+        );
+    }
+}
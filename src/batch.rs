@@ -0,0 +1,148 @@
+use std::process;
+use std::time::SystemTime;
+
+use npuzzle_lib::*;
+use types::ParsedPuzzle;
+use types::Problem;
+use types::Statistic;
+use types::Status;
+
+use crate::parse_goal;
+use crate::search_options;
+use crate::solve;
+use crate::ParsedArgs;
+
+#[derive(Default)]
+struct Totals {
+    puzzles: Statistic,
+    solved: Statistic,
+    unsolvable: Statistic,
+    failed: Statistic,
+    moves: Statistic,
+    closed_states: Statistic,
+}
+
+pub fn run(path: &str, template: &Problem, parsed: &ParsedArgs) {
+    let start_time = SystemTime::now();
+    let batch = input_parser::parse_batch(&path.to_string());
+
+    // a goal read from stdin or a file is shared by every puzzle
+    let fixed_goal = match &parsed.goal as &str {
+        "snail" | "classic" => None,
+        goal => match parse_goal(goal, 0) {
+            Some(g) => Some(g),
+            None => {
+                println!("Error while parsing goal, exiting");
+                process::exit(1);
+            }
+        },
+    };
+
+    let mut totals = Totals::default();
+
+    println!("-----------------");
+    for (name, opt_input) in batch {
+        totals.puzzles += 1;
+
+        let input = match opt_input {
+            Some(e) => e,
+            None => {
+                totals.failed += 1;
+                println!("{} : parse error", name);
+                continue;
+            }
+        };
+
+        let goal = match fixed_goal {
+            Some(ref g) => ParsedPuzzle {
+                container: g.container.clone(),
+                size: g.size,
+            },
+            None => parse_goal(&parsed.goal, input.size).unwrap(),
+        };
+
+        if input.size != goal.size {
+            totals.failed += 1;
+            println!(
+                "{} : mismatched puzzle sizes, expected {}, got {}",
+                name, goal.size, input.size
+            );
+            continue;
+        }
+
+        let explanation = checker::explain(&input.container, &goal.container, input.size);
+        if !explanation.is_solvable() {
+            totals.unsolvable += 1;
+            match explanation.swap {
+                Some((a, b)) => println!("{} : not solvable, swap tiles {} and {} to make it solvable", name, a, b),
+                None => println!("{} : not solvable", name),
+            }
+            continue;
+        }
+
+        let problem = Problem {
+            start: input.container,
+            end: goal.container,
+            size: input.size,
+            heuristic: template.heuristic,
+            g_weight: template.g_weight,
+            h_weight: template.h_weight,
+        };
+
+        let solution = match solve(&problem, parsed, &mut search_options()) {
+            Ok(s) => s,
+            Err(e) => {
+                totals.failed += 1;
+                println!("{} : disk search failed: {}", name, e);
+                continue;
+            }
+        };
+
+        match solution.status {
+            Status::Solved => {}
+            Status::Unsolvable => {
+                totals.unsolvable += 1;
+                println!("{} : not solvable", name);
+                continue;
+            }
+            Status::Cancelled | Status::InProgress => {
+                totals.failed += 1;
+                println!("{} : search cancelled", name);
+                continue;
+            }
+            Status::OutOfBudget => {
+                totals.failed += 1;
+                println!("{} : search ran out of budget", name);
+                continue;
+            }
+        }
+
+        let moves = solution.path.len() - 1;
+        totals.solved += 1;
+        totals.moves += moves;
+        totals.closed_states += solution.closed_states;
+
+        print!("{} : solved in {} moves", name, moves);
+        print!(
+            " ({} closed states, {} max states",
+            solution.closed_states, solution.max_states
+        );
+        println!(", {:?})", solution.time);
+    }
+
+    println!("-----------------");
+    if let Ok(elapsed) = start_time.elapsed() {
+        println!(" - Time elapsed: {:?}", elapsed);
+    }
+    println!(" - Puzzles: {}", totals.puzzles);
+    println!(" - Solved: {}", totals.solved);
+    println!(" - Not solvable: {}", totals.unsolvable);
+    println!(" - Failed: {}", totals.failed);
+    println!(" - Total solution length: {}", totals.moves);
+    println!(" - Total closed states: {}", totals.closed_states);
+    println!("-----------------");
+
+    if totals.failed > 0 {
+        process::exit(1);
+    }
+}
@@ -1,43 +1,127 @@
 extern crate npuzzle_lib;
+extern crate serde_json;
+extern crate termion;
 
+mod batch;
+mod play;
+mod replay;
+mod server;
+mod stream;
+
+use std::borrow::Cow;
 use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::io::Write;
 use std::process;
+use std::time::Duration;
 
 use npuzzle_lib::*;
+use external::DiskConfig;
+use observer::Progress;
+use observer::Reporter;
+use state_space::DistanceTable;
+use types::Atom;
+use types::Heuristic;
+use types::LabeledPuzzle;
 use types::ParsedPuzzle;
 use types::Problem;
+use types::SearchOptions;
+use types::Solution;
 use types::Solver;
+use types::Statistic;
+use types::Status;
 
 pub struct ParsedArgs {
     pub solver: Solver,
     pub input: String,
+    pub format: Option<String>,
     pub goal: String,
+    pub goal_given: bool,
     pub iterations: u64,
+    pub disk: Option<DiskConfig>,
+    pub table: Option<String>,
+    pub batch: Option<String>,
+    pub bench: Option<String>,
+    pub play: bool,
+    pub analyze: bool,
+    pub replay: Option<u64>,
+    pub export: Option<String>,
+    pub moves: Option<String>,
+    pub trace: Option<String>,
+    pub trace_cap: Statistic,
+    pub serve: Option<String>,
+    pub jobs: usize,
+    pub stream: bool,
+    pub output: Option<String>,
+    pub seed: Option<u64>,
 }
 
-const HELP_TEXT: &'static str = "Usage: rustaquin -i [file] -o [file]
+const HELP_TEXT: &str = "Usage: rustaquin -i [file] -o [file]
+
+A file with start: and goal: sections holds both puzzles, its goal being
+used unless --goal is given.
 
 Arguments:
 --input | -i [stdin, file, random]
+--format [auto, text, json, line, csv]
 --goal | -o [snail, classic, stdin, file]
 --heuristic | -h [conflicts, hamming, manhattan]
 --uniform | -u
 --greedy | -g
---iterations | -n [number]";
+--iterations | -n [number]
+--seed [number]
+--output [file]
+--disk | -d [directory]
+--memory | -m [megabytes]
+--table | -t [file]
+--batch | -b [directory, file]
+--bench [table, csv]
+--play | -p
+--analyze
+--replay | -r [milliseconds]
+--export | -e [file.svg, file.html]
+--moves [string]
+--trace [file.dot, file.jsonl]
+--trace-cap [number]
+--serve [port, loopback address, unix:path]
+--jobs [number]
+--stream";
 
 fn parse_args(args: Vec<String>) -> (Problem, ParsedArgs) {
     let mut problem = Problem {
         start: vec![],
         end: vec![],
         size: 0,
-        heuristic: heuristics::linear_conflicts,
+        heuristic: Heuristic::Conflicts,
         g_weight: 1,
         h_weight: 1,
     };
 
     let mut input: String = "stdin".to_string();
+    let mut format: Option<String> = None;
     let mut goal: String = "snail".to_string();
+    let mut goal_given: bool = false;
     let mut iterations: u64 = 10000;
+    let mut disk: Option<String> = None;
+    let mut memory: Option<usize> = None;
+    let mut table: Option<String> = None;
+    let mut batch: Option<String> = None;
+    let mut bench: Option<String> = None;
+    let mut play: bool = false;
+    let mut analyze: bool = false;
+    let mut replay: Option<u64> = None;
+    let mut export: Option<String> = None;
+    let mut moves: Option<String> = None;
+    let mut trace: Option<String> = None;
+    let mut trace_cap: Statistic = 100000;
+    let mut serve: Option<String> = None;
+    let mut jobs: usize = 4;
+    let mut stream: bool = false;
+    let mut output: Option<String> = None;
+    let mut seed: Option<u64> = None;
 
     if args.len() == 1 {
         println!("{}", HELP_TEXT);
@@ -47,7 +131,7 @@ fn parse_args(args: Vec<String>) -> (Problem, ParsedArgs) {
     for i in 1..args.len() {
         let cur = &args[i];
 
-        match &cur as &str {
+        match cur as &str {
             "--help" => {
                 println!("{}", HELP_TEXT);
                 process::exit(0);
@@ -61,9 +145,28 @@ fn parse_args(args: Vec<String>) -> (Problem, ParsedArgs) {
                     }
                 }
             }
+            "--format" => {
+                match args.get(i + 1) {
+                    Some(val) if val == "auto" => format = None,
+                    Some(val) if input_parser::format_from_name(val).is_some() => {
+                        format = Some(val.to_string())
+                    }
+                    Some(val) => {
+                        println!("format {} is not valid", val);
+                        process::exit(1);
+                    }
+                    None => {
+                        println!("format cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
             "--goal" | "-o" => {
                 match args.get(i + 1) {
-                    Some(val) => goal = val.to_string(),
+                    Some(val) => {
+                        goal = val.to_string();
+                        goal_given = true;
+                    }
                     None => {
                         println!("goal cannot be blank");
                         process::exit(1);
@@ -73,11 +176,9 @@ fn parse_args(args: Vec<String>) -> (Problem, ParsedArgs) {
             "--heuristic" | "-h" => {
                 match args.get(i + 1) {
                     Some(cur) => {
-                        match &cur as &str {
-                            "hamming" => problem.heuristic = heuristics::hamming,
-                            "manhattan" => problem.heuristic = heuristics::manhattan,
-                            "conflicts" => problem.heuristic = heuristics::linear_conflicts,
-                            _ => {
+                        match heuristics::from_name(cur) {
+                            Some(h) => problem.heuristic = h,
+                            None => {
                                 println!("heuristic {} is not valid", cur);
                                 process::exit(1);
                             }
@@ -91,6 +192,9 @@ fn parse_args(args: Vec<String>) -> (Problem, ParsedArgs) {
             }
             "--uniform" | "-u" => problem.h_weight = 0,
             "--greedy" | "-g" => problem.g_weight = 0,
+            "--play" | "-p" => play = true,
+            "--analyze" => analyze = true,
+            "--stream" => stream = true,
             "--iterations" | "-n" => {
                 match args.get(i + 1) {
                     Some(cur) => {
@@ -108,88 +212,474 @@ fn parse_args(args: Vec<String>) -> (Problem, ParsedArgs) {
                     }
                 }
             }
+            "--seed" => {
+                match args.get(i + 1) {
+                    Some(cur) => {
+                        match cur.parse::<u64>() {
+                            Ok(val) => seed = Some(val),
+                            Err(_) => {
+                                println!("seed {} is not valid", cur);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    None => {
+                        println!("seed cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--output" => {
+                match args.get(i + 1) {
+                    Some(val) => output = Some(val.to_string()),
+                    None => {
+                        println!("output cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--disk" | "-d" => {
+                match args.get(i + 1) {
+                    Some(val) => disk = Some(val.to_string()),
+                    None => {
+                        println!("disk cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--memory" | "-m" => {
+                match args.get(i + 1) {
+                    Some(cur) => {
+                        match cur.parse::<usize>() {
+                            Ok(val) if val > 0 => memory = Some(val),
+                            _ => {
+                                println!("memory {} is not valid", cur);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    None => {
+                        println!("memory cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--table" | "-t" => {
+                match args.get(i + 1) {
+                    Some(val) => table = Some(val.to_string()),
+                    None => {
+                        println!("table cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--batch" | "-b" => {
+                match args.get(i + 1) {
+                    Some(val) => batch = Some(val.to_string()),
+                    None => {
+                        println!("batch cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--bench" => {
+                match args.get(i + 1).map(|val| val as &str) {
+                    Some("table") | Some("csv") => bench = args.get(i + 1).cloned(),
+                    Some(cur) => {
+                        println!("bench output {} is not valid", cur);
+                        process::exit(1);
+                    }
+                    None => {
+                        println!("bench cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--replay" | "-r" => {
+                match args.get(i + 1) {
+                    Some(cur) => {
+                        match cur.parse::<u64>() {
+                            Ok(val) if val > 0 => replay = Some(val),
+                            _ => {
+                                println!("replay speed {} is not valid", cur);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    None => {
+                        println!("replay speed cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--export" | "-e" => {
+                match args.get(i + 1) {
+                    Some(val) if val.ends_with(".svg") || val.ends_with(".html") => {
+                        export = Some(val.to_string())
+                    }
+                    Some(val) => {
+                        println!("export file {} must end with .svg or .html", val);
+                        process::exit(1);
+                    }
+                    None => {
+                        println!("export cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--moves" => {
+                match args.get(i + 1) {
+                    Some(val) => moves = Some(val.to_string()),
+                    None => {
+                        println!("moves cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--trace" => {
+                match args.get(i + 1) {
+                    Some(val) if val.ends_with(".dot") || val.ends_with(".jsonl") => {
+                        trace = Some(val.to_string())
+                    }
+                    Some(val) => {
+                        println!("trace file {} must end with .dot or .jsonl", val);
+                        process::exit(1);
+                    }
+                    None => {
+                        println!("trace cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--trace-cap" => {
+                match args.get(i + 1) {
+                    Some(cur) => {
+                        match cur.parse::<Statistic>() {
+                            Ok(val) => trace_cap = val,
+                            Err(_) => {
+                                println!("trace cap {} is not valid", cur);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    None => {
+                        println!("trace cap cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--serve" => {
+                match args.get(i + 1) {
+                    Some(val) => serve = Some(val.to_string()),
+                    None => {
+                        println!("serve address cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
+            "--jobs" => {
+                match args.get(i + 1) {
+                    Some(cur) => {
+                        match cur.parse::<usize>() {
+                            Ok(val) if val > 0 => jobs = val,
+                            _ => {
+                                println!("jobs {} is not valid", cur);
+                                process::exit(1);
+                            }
+                        }
+                    }
+                    None => {
+                        println!("jobs cannot be blank");
+                        process::exit(1);
+                    }
+                }
+            }
             _ => {}
         }
     }
 
+    // the disk search keeps no trace, the file would stay empty
+    if disk.is_some() && trace.is_some() {
+        println!("--trace cannot be used with --disk");
+        process::exit(1);
+    }
+
+    let disk = disk.map(|dir| {
+        let mut config = DiskConfig {
+            temp_dir: PathBuf::from(dir),
+            ..DiskConfig::default()
+        };
+
+        if let Some(megabytes) = memory {
+            config.memory = megabytes * 1024 * 1024;
+        }
+
+        config
+    });
+
     (
         problem,
         ParsedArgs {
-            solver: solver::astar,
+            solver: solver::astar_with,
             input,
+            format,
             goal,
+            goal_given,
             iterations,
+            disk,
+            table,
+            batch,
+            bench,
+            play,
+            analyze,
+            replay,
+            export,
+            moves,
+            trace,
+            trace_cap,
+            serve,
+            jobs,
+            stream,
+            output,
+            seed,
         },
     )
 }
 
+pub fn parse_goal(goal: &str, size: Atom) -> Option<ParsedPuzzle> {
+    match goal {
+        "stdin" => {
+            println!("Reading stdin for goal state...");
+            input_parser::parse(None)
+        }
+        "snail" => Some(ParsedPuzzle {
+            container: generator::snail(size),
+            size,
+        }),
+        "classic" => Some(ParsedPuzzle {
+            container: generator::classic(size),
+            size,
+        }),
+        _ => input_parser::parse(Some(&goal.to_string())),
+    }
+}
+
+pub fn solve<'a>(
+    problem: &'a Problem,
+    parsed: &ParsedArgs,
+    options: &mut SearchOptions,
+) -> io::Result<Solution<'a>> {
+    let solution = match parsed.disk {
+        Some(ref config) => external::astar(problem, config, options),
+        None => Ok((parsed.solver)(problem, options)),
+    };
+
+    if options.reporter.is_some() {
+        eprint!("{}\r", termion::clear::CurrentLine);
+    }
+
+    solution
+}
+
+fn print_progress(p: &Progress) {
+    eprint!(
+        "{}\rf = {} | expanded {} | open {} | closed {} | {:.0} nodes/s | {:.1}s",
+        termion::clear::CurrentLine,
+        p.f_bound,
+        p.expanded,
+        p.open_states,
+        p.closed_states,
+        p.nodes_per_second,
+        p.elapsed.as_secs_f64()
+    );
+    let _ = io::stderr().flush();
+}
+
+// Live progress on stderr, only when it is a terminal
+pub fn search_options() -> SearchOptions {
+    let mut options = SearchOptions::default();
+
+    if termion::is_tty(&io::stderr()) {
+        options.reporter = Some(Reporter::new(
+            Box::new(print_progress),
+            0,
+            Duration::from_secs(1),
+        ));
+    }
+
+    options
+}
+
+fn write_trace(file: &str, options: &SearchOptions, size: Atom) {
+    let trace = match options.trace {
+        Some(ref t) => t,
+        None => return,
+    };
+    let content = if file.ends_with(".dot") {
+        trace::to_dot(trace, size)
+    } else {
+        trace::to_jsonl(trace)
+    };
+
+    if let Err(e) = fs::write(file, content) {
+        println!("File : {} : {}", file, e);
+        process::exit(1);
+    }
+    println!(
+        "Trace of {} expansions written to {}{}",
+        trace.expansions.len(),
+        file,
+        if trace.truncated { " (truncated)" } else { "" }
+    );
+}
+
+fn write_puzzle(file: &str, puzzle: &ParsedPuzzle, parsed: &ParsedArgs, random: bool) {
+    let header = if random {
+        writer::Header {
+            generator: Some(format!(
+                "random walk of {} moves from the {} goal",
+                parsed.iterations, parsed.goal
+            )),
+            seed: parsed.seed,
+            optimal_length: None,
+        }
+    } else {
+        writer::Header::default()
+    };
+
+    if let Err(e) = fs::write(file, writer::to_string(puzzle, &header)) {
+        println!("File : {} : {}", file, e);
+        process::exit(1);
+    }
+    println!("Puzzle written to {}", file);
+}
+
+fn build_table(goal: &str, file: &str) {
+    let size = state_space::TABLE_SIZE;
+
+    let goal = match parse_goal(goal, size) {
+        Some(e) => e,
+        None => {
+            println!("Error while parsing, exiting");
+            process::exit(1);
+        }
+    };
+
+    if goal.size != size {
+        println!("Distance tables only support {}x{} puzzles", size, size);
+        process::exit(1);
+    }
+
+    println!("Enumerating every state reachable from the goal...");
+    let table = DistanceTable::build(&goal.container);
+    state_space::print_report(&table);
+
+    if let Err(e) = table.save(Path::new(file)) {
+        println!("File : {} : {}", file, e);
+        process::exit(1);
+    }
+    println!("Distance table written to {}", file);
+}
+
 fn main() {
     let (mut problem, parsed) = parse_args(env::args().collect());
 
+    if let Some(ref file) = parsed.table {
+        build_table(&parsed.goal, file);
+        return;
+    }
+
+    if let Some(ref output) = parsed.bench {
+        let records = bench::run(&bench::suite());
+
+        match output as &str {
+            "csv" => bench::print_csv(&records),
+            _ => bench::print_table(&records),
+        }
+        return;
+    }
+
+    if let Some(ref address) = parsed.serve {
+        let config = server::ServerConfig {
+            address: address.to_string(),
+            jobs: parsed.jobs,
+        };
+
+        if let Err(e) = server::run(&config) {
+            println!("Server error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if parsed.stream {
+        stream::run(&problem, &parsed);
+        return;
+    }
+
+    if let Some(ref path) = parsed.batch {
+        batch::run(path, &problem, &parsed);
+        return;
+    }
+
     // 1. Input
-    let opt_input: Option<ParsedPuzzle>;
+    let opt_input: Option<LabeledPuzzle>;
     let mut random: bool = false;
 
     match &parsed.input as &str {
         "stdin" => {
             println!("Reading stdin for input state...");
-            opt_input = input_parser::parse(None)
+            opt_input = input_parser::parse_labeled_as(None, parsed.format.as_deref())
         }
         "random" => {
             random = true;
-            opt_input = Some(ParsedPuzzle {
-                container: vec![],
-                size: 3,
+            opt_input = Some(LabeledPuzzle {
+                start: ParsedPuzzle {
+                    container: vec![],
+                    size: 3,
+                },
+                goal: None,
+                metadata: Vec::new(),
             });
         }
-        _ => opt_input = input_parser::parse(Some(&parsed.input)),
+        _ => opt_input = input_parser::parse_labeled_as(Some(&parsed.input), parsed.format.as_deref()),
     }
 
-    let mut input;
-    match opt_input {
-        Some(e) => input = e,
+    let labeled = match opt_input {
+        Some(e) => e,
         None => {
             println!("Error while parsing, exiting");
             process::exit(1);
         }
-    }
+    };
+    let expected_length = labeled.expected_length();
+    let mut input = labeled.start;
 
     // 2. GOAL
-    let opt_goal: Option<ParsedPuzzle>;
-
-    match &parsed.goal as &str {
-        "stdin" => {
-            println!("Reading stdin for goal state...");
-            opt_goal = input_parser::parse(None)
-        }
-        "snail" => {
-            opt_goal = Some(ParsedPuzzle {
-                container: generator::snail(input.size),
-                size: input.size,
-            })
-        }
-        "classic" => {
-            opt_goal = Some(ParsedPuzzle {
-                container: generator::classic(input.size),
-                size: input.size,
-            })
-        }
-        _ => opt_goal = input_parser::parse(Some(&parsed.goal)),
-    }
-
-    let goal;
-    match opt_goal {
-        Some(e) => goal = e,
-        None => {
-            println!("Error while parsing, exiting");
-            process::exit(1);
-        }
-    }
+    let goal = match labeled.goal {
+        Some(g) if !parsed.goal_given => g,
+        _ => match parse_goal(&parsed.goal, input.size) {
+            Some(e) => e,
+            None => {
+                println!("Error while parsing, exiting");
+                process::exit(1);
+            }
+        },
+    };
 
     // 2.1 Generate random if needed
     if random {
         println!("Generating random puzzle with {} iterations...", parsed.iterations);
-        input = generator::generate_valid_puzzle(&goal, parsed.iterations);
+        input = match parsed.seed {
+            Some(seed) => generator::generate_seeded_puzzle(&goal, parsed.iterations, seed),
+            None => generator::generate_valid_puzzle(&goal, parsed.iterations),
+        };
+    }
+
+    // 2.2 Save the puzzle instead of solving it if asked to
+    if let Some(ref file) = parsed.output {
+        write_puzzle(file, &input, &parsed, random);
+        return;
     }
 
     // 3. Check sizes
@@ -201,23 +691,124 @@ fn main() {
         process::exit(1);
     }
 
+    // 3.1 Describe the puzzle instead of solving it if asked to
+    if parsed.analyze {
+        let analysis = checker::analyze(&input.container, &goal.container, input.size);
+
+        print!("{}", checker::format_analysis(&analysis));
+        return;
+    }
+
     // 4. Check solvability
-    if !checker::is_solvable(&input.container, &goal.container, input.size) {
+    let explanation = checker::explain(&input.container, &goal.container, input.size);
+    if !explanation.is_solvable() {
         println!("Puzzle not solvable");
+        print!("{}", checker::format_explanation(&explanation));
         process::exit(1);
     }
 
+    // 4.1 Play by hand if asked to
+    if parsed.play {
+        let game = game::Game::new(input.container, goal.container, input.size).unwrap();
+
+        if let Err(e) = play::run(game, problem.heuristic) {
+            println!("Terminal error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     // 5. Solve
     problem.start = input.container;
     problem.end = goal.container;
     problem.size = input.size;
 
-    let solution = (parsed.solver)(&problem);
+    let mut options = search_options();
+    if parsed.trace.is_some() {
+        options.trace = Some(trace::Trace::new(parsed.trace_cap));
+    }
 
-    if solution.path.len() == 0 {
-        println!("Puzzle not solvable");
-        process::exit(1);
+    let solution = match parsed.moves {
+        Some(ref moves) => match game::apply_moves(&problem.start, problem.size, moves) {
+            Some(ref path) if path.last() != Some(&problem.end) => {
+                println!("moves {} do not reach the goal", moves);
+                process::exit(1);
+            }
+            Some(path) => Solution {
+                problem: Cow::Borrowed(&problem),
+                status: Status::Solved,
+                path,
+                max_states: 0,
+                opened_states: 0,
+                current_open_states: 0,
+                closed_states: 0,
+                time: Duration::from_secs(0),
+            },
+            None => {
+                println!("moves {} are not valid for this puzzle", moves);
+                process::exit(1);
+            }
+        },
+        None => match solve(&problem, &parsed, &mut options) {
+            Ok(s) => s,
+            Err(e) => {
+                println!("Disk search failed: {}", e);
+                process::exit(1);
+            }
+        },
+    };
+
+    if let Some(ref file) = parsed.trace {
+        write_trace(file, &options, problem.size);
+    }
+
+    match solution.status {
+        Status::Solved => {}
+        Status::Unsolvable => {
+            println!("Puzzle not solvable");
+            process::exit(1);
+        }
+        Status::Cancelled | Status::InProgress => {
+            println!("Search cancelled");
+            process::exit(1);
+        }
+        Status::OutOfBudget => {
+            println!("Search ran out of budget");
+            process::exit(1);
+        }
+    }
+
+    if let Some(ref file) = parsed.export {
+        let content = if file.ends_with(".html") {
+            export::solution_html(&solution)
+        } else {
+            export::solution_svg(&solution)
+        };
+
+        if let Err(e) = fs::write(file, content) {
+            println!("File : {} : {}", file, e);
+            process::exit(1);
+        }
+        println!("Solution exported to {}", file);
+        return;
+    }
+
+    if let Some(delay) = parsed.replay {
+        if let Err(e) = replay::run(&solution, delay) {
+            println!("Terminal error: {}", e);
+            process::exit(1);
+        }
+        return;
     }
 
     solver::print_solution(&solution);
+
+    if let Some(expected) = expected_length {
+        let length = solution.path.len() - 1;
+
+        if length != expected {
+            println!("Expected a solution of {} moves, found {}", expected, length);
+            process::exit(1);
+        }
+    }
 }
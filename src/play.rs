@@ -0,0 +1,67 @@
+use std::io;
+use std::io::Write;
+
+use termion::clear;
+use termion::cursor;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+
+use npuzzle_lib::*;
+use game::Direction;
+use game::Game;
+use types::Heuristic;
+
+const KEYS_TEXT: &str = "arrows / wasd: move | h: hint | q: quit";
+
+fn draw<W: Write>(out: &mut W, game: &Game, status: &str) -> io::Result<()> {
+    write!(out, "{}{}", clear::All, cursor::Goto(1, 1))?;
+    write!(out, "{}\r\n", game.render().replace('\n', "\r\n"))?;
+    write!(out, "Moves: {}\r\n{}\r\n{}\r\n", game.moves, KEYS_TEXT, status)?;
+    out.flush()
+}
+
+pub fn run(mut game: Game, heuristic: Heuristic) -> io::Result<()> {
+    // keys come from the terminal, stdin may have been used for the puzzle
+    let tty = termion::get_tty()?;
+    let mut out = io::stdout().into_raw_mode()?;
+    let mut status = String::new();
+
+    draw(&mut out, &game, &status)?;
+
+    for key in tty.keys() {
+        let dir = match key? {
+            Key::Up | Key::Char('w') => Some(Direction::Up),
+            Key::Down | Key::Char('s') => Some(Direction::Down),
+            Key::Left | Key::Char('a') => Some(Direction::Left),
+            Key::Right | Key::Char('d') => Some(Direction::Right),
+            Key::Char('h') => {
+                status = match game.hint(heuristic) {
+                    Some(d) => format!("Hint: {:?}", d),
+                    None => "Hint: already solved".to_string(),
+                };
+                None
+            }
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => break,
+            _ => None,
+        };
+
+        if let Some(d) = dir {
+            if game.play(d) {
+                status.clear();
+            } else {
+                status = format!("Cannot move {:?}", d);
+            }
+        }
+
+        if game.is_solved() {
+            status = format!("Solved in {} moves!", game.moves);
+            draw(&mut out, &game, &status)?;
+            break;
+        }
+        draw(&mut out, &game, &status)?;
+    }
+
+    write!(out, "\r\n")?;
+    out.flush()
+}
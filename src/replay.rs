@@ -0,0 +1,103 @@
+use std::io;
+use std::io::Write;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use termion::clear;
+use termion::cursor;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::style;
+
+use npuzzle_lib::*;
+use game::moved_tile;
+use types::Solution;
+
+const KEYS_TEXT: &str = "space: pause | left / right: step | + / -: speed | q: quit";
+
+fn draw<W: Write>(out: &mut W, s: &Solution, step: usize, delay: u64, paused: bool) -> io::Result<()> {
+    let puzzle = &s.path[step];
+    let size = s.problem.size as usize;
+    let width = puzzle.iter().max().map_or(1, |v| v.to_string().len());
+    let moved = if step > 0 {
+        moved_tile(&s.path[step - 1], puzzle)
+    } else {
+        None
+    };
+
+    write!(out, "{}{}", clear::All, cursor::Goto(1, 1))?;
+    for (i, v) in puzzle.iter().enumerate() {
+        if Some(*v) == moved {
+            write!(out, "{}{:>3$}{}", style::Invert, v, style::Reset, width)?;
+        } else {
+            write!(out, "{:>1$}", v, width)?;
+        }
+        write!(out, "{}", if i % size == size - 1 { "\r\n" } else { " " })?;
+    }
+
+    write!(
+        out,
+        "\r\nStep {} / {} | {} ms per step{}\r\n{}\r\n",
+        step,
+        s.path.len() - 1,
+        delay,
+        if paused { " | paused" } else { "" },
+        KEYS_TEXT
+    )?;
+    out.flush()
+}
+
+pub fn run(s: &Solution, mut delay: u64) -> io::Result<()> {
+    let tty = termion::get_tty()?;
+    let mut out = io::stdout().into_raw_mode()?;
+    let (tx, rx) = mpsc::channel();
+
+    // keys are read on their own thread, so that the animation keeps going
+    thread::spawn(move || {
+        for key in tty.keys() {
+            match key {
+                Ok(k) => {
+                    if tx.send(k).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    let last = s.path.len() - 1;
+    let mut step = 0;
+    let mut paused = false;
+
+    loop {
+        draw(&mut out, s, step, delay, paused || step == last)?;
+
+        match rx.recv_timeout(Duration::from_millis(delay)) {
+            Ok(Key::Char(' ')) => paused = !paused,
+            Ok(Key::Right) | Ok(Key::Char('l')) => {
+                paused = true;
+                step = ::std::cmp::min(step + 1, last);
+            }
+            Ok(Key::Left) | Ok(Key::Char('h')) => {
+                paused = true;
+                step = step.saturating_sub(1);
+            }
+            Ok(Key::Char('+')) => delay = ::std::cmp::max(delay / 2, 10),
+            Ok(Key::Char('-')) => delay = ::std::cmp::min(delay * 2, 10000),
+            Ok(Key::Char('q')) | Ok(Key::Esc) | Ok(Key::Ctrl('c')) => break,
+            Ok(_) => {}
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if !paused && step < last {
+                    step += 1;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    write!(out, "\r\n")?;
+    out.flush()
+}
@@ -0,0 +1,558 @@
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde_json::json;
+use serde_json::Value;
+
+use npuzzle_lib::*;
+use observer::Progress;
+use observer::Reporter;
+use solver::Budget;
+use solver::Limit;
+use solver::Search;
+use types::Atom;
+use types::Heuristic;
+use types::ParsedPuzzle;
+use types::Problem;
+use types::Puzzle;
+use types::SearchOptions;
+use types::Statistic;
+use types::Status;
+use types::MAX_PUZZLE_SIZE;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// Upper bounds of what a single request may ask for
+const MAX_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_EXPANDED: Statistic = 20_000_000;
+const MAX_ITERATIONS: u64 = 1_000_000;
+// The states kept by a search grow with the number of tiles, past 5x5 the
+// expansion budget alone would let a request take tens of gigabytes
+const MAX_SOLVE_SIZE: Atom = 5;
+// Keeps g * g_weight + h * h_weight far from overflowing, even on the largest
+// puzzles
+const MAX_WEIGHT: u64 = 1000;
+
+// Pause after a failed accept, so that running out of file descriptors does
+// not turn into a busy loop
+const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+pub struct ServerConfig {
+    pub address: String,
+    pub jobs: usize,
+}
+
+type Writer = Arc<Mutex<Box<dyn Write + Send>>>;
+type RpcResult = Result<Value, (i64, String)>;
+
+// Counting semaphore bounding the number of searches running at once
+struct Slots {
+    free: Mutex<usize>,
+    available: Condvar,
+}
+
+// Taken slot, given back when dropped, even by a search that panicked
+struct Slot<'a> {
+    slots: &'a Slots,
+}
+
+impl Slots {
+    fn acquire(&self) -> Slot<'_> {
+        let mut free = self.free.lock().unwrap();
+
+        while *free == 0 {
+            free = self.available.wait(free).unwrap();
+        }
+        *free -= 1;
+
+        Slot { slots: self }
+    }
+}
+
+impl<'a> Drop for Slot<'a> {
+    fn drop(&mut self) {
+        // the count stays right even if a panic poisoned the lock
+        let mut free = match self.slots.free.lock() {
+            Ok(f) => f,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        *free += 1;
+        self.slots.available.notify_one();
+    }
+}
+
+fn send(writer: &Writer, message: &Value) {
+    let line = format!("{}\n", message);
+    let mut w = writer.lock().unwrap();
+
+    // a client that went away only loses its own answers
+    let _ = w.write_all(line.as_bytes());
+    let _ = w.flush();
+}
+
+fn invalid(message: &str) -> (i64, String) {
+    (INVALID_PARAMS, message.to_string())
+}
+
+fn get_u64(params: &Value, key: &str) -> Result<Option<u64>, (i64, String)> {
+    match params.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(v) => match v.as_u64() {
+            Some(n) => Ok(Some(n)),
+            None => Err(invalid(&format!("{} must be a positive integer", key))),
+        },
+    }
+}
+
+fn get_weight(params: &Value, key: &str) -> Result<u64, (i64, String)> {
+    match get_u64(params, key)? {
+        Some(w) if w > MAX_WEIGHT => Err(invalid(&format!("{} must be at most {}", key, MAX_WEIGHT))),
+        w => Ok(w.unwrap_or(1)),
+    }
+}
+
+fn get_size(params: &Value) -> Result<Atom, (i64, String)> {
+    match get_u64(params, "size")? {
+        Some(s) if s >= 3 && s <= MAX_PUZZLE_SIZE as u64 => Ok(s as Atom),
+        Some(_) => Err(invalid(&format!("size must be between 3 and {}", MAX_PUZZLE_SIZE))),
+        None => Err(invalid("size is missing")),
+    }
+}
+
+// Tiles must be a permutation of 0..size * size
+fn to_puzzle(value: Option<&Value>, key: &str, size: Atom) -> Result<Puzzle, (i64, String)> {
+    let values = match value.and_then(|v| v.as_array()) {
+        Some(a) => a,
+        None => return Err(invalid(&format!("{} must be an array of numbers", key))),
+    };
+    let count = size as usize * size as usize;
+
+    if values.len() != count {
+        return Err(invalid(&format!("{} must hold {} numbers", key, count)));
+    }
+
+    let mut seen = vec![false; count];
+    let mut puzzle = Vec::with_capacity(count);
+
+    for v in values {
+        match v.as_u64() {
+            Some(n) if (n as usize) < count && !seen[n as usize] => {
+                seen[n as usize] = true;
+                puzzle.push(n as Atom);
+            }
+            _ => {
+                return Err(invalid(&format!(
+                    "{} must hold every number from 0 to {} once",
+                    key,
+                    count - 1
+                )))
+            }
+        }
+    }
+
+    Ok(puzzle)
+}
+
+fn get_goal(params: &Value, size: Atom) -> Result<Puzzle, (i64, String)> {
+    match params.get("goal") {
+        None | Some(Value::Null) => Ok(generator::snail(size)),
+        Some(Value::String(name)) => match name as &str {
+            "snail" => Ok(generator::snail(size)),
+            "classic" => Ok(generator::classic(size)),
+            _ => Err(invalid(&format!("goal {} is not valid", name))),
+        },
+        goal => to_puzzle(goal, "goal", size),
+    }
+}
+
+// Start, goal and size shared by most methods
+fn get_boards(params: &Value) -> Result<(Puzzle, Puzzle, Atom), (i64, String)> {
+    let size = get_size(params)?;
+    let tiles = to_puzzle(params.get("tiles"), "tiles", size)?;
+    let goal = get_goal(params, size)?;
+
+    Ok((tiles, goal, size))
+}
+
+fn solve(id: &Value, params: &Value, writer: &Writer, slots: &Slots) -> RpcResult {
+    let (start, goal, size) = get_boards(params)?;
+    if size > MAX_SOLVE_SIZE {
+        return Err(invalid(&format!("size must be at most {} to solve", MAX_SOLVE_SIZE)));
+    }
+    let heuristic = match params.get("heuristic").and_then(|h| h.as_str()) {
+        None => Heuristic::Conflicts,
+        Some(name) => match heuristics::from_name(name) {
+            Some(h) => h,
+            None => return Err(invalid(&format!("heuristic {} is not valid", name))),
+        },
+    };
+    let g_weight = get_weight(params, "g_weight")?;
+    let h_weight = get_weight(params, "h_weight")?;
+
+    let budget = params.get("budget").cloned().unwrap_or(Value::Null);
+    let timeout = get_u64(&budget, "timeout_ms")?
+        .map_or(MAX_TIMEOUT, Duration::from_millis)
+        .min(MAX_TIMEOUT);
+    let max_expanded = get_u64(&budget, "expanded")?
+        .map_or(MAX_EXPANDED, |n| n as Statistic)
+        .min(MAX_EXPANDED);
+    let progress = get_u64(params, "progress_ms")?;
+
+    if !checker::is_solvable(&start, &goal, size) {
+        return Ok(json!({ "status": "unsolvable" }));
+    }
+
+    let problem = Problem {
+        start,
+        end: goal,
+        size,
+        heuristic,
+        g_weight,
+        h_weight,
+    };
+
+    let mut options = SearchOptions::default();
+    if let Some(ms) = progress {
+        let id = id.clone();
+        let writer = writer.clone();
+
+        options.reporter = Some(Reporter::new(
+            Box::new(move |p: &Progress| {
+                send(
+                    &writer,
+                    &json!({
+                        "jsonrpc": "2.0",
+                        "method": "progress",
+                        "params": {
+                            "id": id,
+                            "f": p.f_bound,
+                            "expanded": p.expanded,
+                            "open": p.open_states,
+                            "closed": p.closed_states,
+                            "nodes_per_second": p.nodes_per_second,
+                            "elapsed_ms": p.elapsed.as_millis() as u64,
+                        }
+                    }),
+                )
+            }),
+            0,
+            Duration::from_millis(ms),
+        ));
+    }
+
+    let slot = slots.acquire();
+    let started = Instant::now();
+    let mut search = Search::new(&problem);
+    let budget = Budget {
+        expanded: Some(max_expanded),
+        time: Some(timeout),
+    };
+    let limit = search.run(&mut options, &budget);
+    drop(slot);
+
+    let solution = search.solution();
+    let mut result = json!({
+        "status": match solution.status {
+            Status::Solved => "solved",
+            Status::Unsolvable => "unsolvable",
+            Status::Cancelled => "cancelled",
+            Status::OutOfBudget => "out_of_budget",
+            Status::InProgress => "in_progress",
+        },
+        "closed_states": solution.closed_states,
+        "max_states": solution.max_states,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    });
+
+    if solution.status == Status::Solved {
+        result["moves"] = json!(game::to_moves(&solution.path, size));
+        result["length"] = json!(solution.path.len() - 1);
+    }
+    match limit {
+        Some(Limit::Expanded) => result["budget"] = json!("expanded"),
+        Some(Limit::Time) => result["budget"] = json!("timeout"),
+        _ => {}
+    }
+
+    Ok(result)
+}
+
+fn generate(params: &Value) -> RpcResult {
+    let size = get_size(params)?;
+    let goal = ParsedPuzzle {
+        container: get_goal(params, size)?,
+        size,
+    };
+    let iterations = get_u64(params, "iterations")?.unwrap_or(10000);
+
+    if iterations > MAX_ITERATIONS {
+        return Err(invalid(&format!("iterations must be at most {}", MAX_ITERATIONS)));
+    }
+
+    let puzzle = match get_u64(params, "seed")? {
+        Some(seed) => generator::generate_seeded_puzzle(&goal, iterations, seed),
+        None => generator::generate_valid_puzzle(&goal, iterations),
+    };
+
+    Ok(json!({ "size": size, "tiles": puzzle.container }))
+}
+
+fn check_solvable(params: &Value) -> RpcResult {
+    let (start, goal, size) = get_boards(params)?;
+
+    Ok(json!({ "solvable": checker::is_solvable(&start, &goal, size) }))
+}
+
+fn verify_solution(params: &Value) -> RpcResult {
+    let (start, goal, size) = get_boards(params)?;
+    let moves = match params.get("moves").and_then(|m| m.as_str()) {
+        Some(m) => m,
+        None => return Err(invalid("moves must be a string")),
+    };
+
+    Ok(match game::apply_moves(&start, size, moves) {
+        Some(path) => json!({
+            "valid": true,
+            "solved": path.last() == Some(&goal),
+            "length": path.len() - 1,
+        }),
+        None => json!({ "valid": false, "solved": false }),
+    })
+}
+
+fn dispatch(line: &str, writer: &Writer, slots: &Slots) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(r) => r,
+        Err(e) => {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": e.to_string() }
+            })
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+
+    let result = match request.get("method").and_then(|m| m.as_str()) {
+        Some("solve") => solve(&id, &params, writer, slots),
+        Some("generate") => generate(&params),
+        Some("check-solvable") => check_solvable(&params),
+        Some("verify-solution") => verify_solution(&params),
+        Some(method) => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        None => Err((INVALID_REQUEST, "method is missing".to_string())),
+    };
+
+    match result {
+        Ok(value) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message }
+        }),
+    }
+}
+
+// One request per line, answered in order on the same connection
+fn handle<R: BufRead>(reader: R, writer: Writer, slots: &Slots) {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = dispatch(&line, &writer, slots);
+        send(&writer, &response);
+    }
+}
+
+// Serves a new connection on its own thread. A connection that failed is only
+// logged, the server keeps accepting the next ones.
+fn accept<S>(stream: io::Result<S>, try_clone: fn(&S) -> io::Result<S>, slots: &Arc<Slots>)
+where
+    S: Read + Write + Send + 'static,
+{
+    let (reader, writer) = match stream.and_then(|s| try_clone(&s).map(|w| (s, w))) {
+        Ok(pair) => pair,
+        Err(e) => {
+            eprintln!("Could not accept a connection: {}", e);
+            thread::sleep(ACCEPT_RETRY);
+            return;
+        }
+    };
+    let writer: Writer = Arc::new(Mutex::new(Box::new(writer)));
+    let slots = slots.clone();
+
+    thread::spawn(move || handle(BufReader::new(reader), writer, &slots));
+}
+
+// The server has no authentication, so it only listens where no other machine
+// can reach it
+fn loopback_addresses(address: &str) -> io::Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> = address.to_socket_addrs()?.collect();
+
+    if addresses.is_empty() || addresses.iter().any(|a| !a.ip().is_loopback()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a loopback address", address),
+        ));
+    }
+
+    Ok(addresses)
+}
+
+// Listens on `unix:<path>` or on a loopback TCP address, a bare port meaning
+// localhost
+pub fn run(config: &ServerConfig) -> io::Result<()> {
+    let slots = Arc::new(Slots {
+        free: Mutex::new(config.jobs),
+        available: Condvar::new(),
+    });
+
+    if let Some(path) = config.address.strip_prefix("unix:") {
+        let listener = UnixListener::bind(path)?;
+        println!("Listening on {}", config.address);
+
+        for stream in listener.incoming() {
+            accept(stream, UnixStream::try_clone, &slots);
+        }
+    } else {
+        let address = if config.address.parse::<u16>().is_ok() {
+            format!("127.0.0.1:{}", config.address)
+        } else {
+            config.address.clone()
+        };
+        let listener = TcpListener::bind(&loopback_addresses(&address)?[..])?;
+        println!("Listening on {}", listener.local_addr()?);
+
+        for stream in listener.incoming() {
+            accept(stream, TcpStream::try_clone, &slots);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(jobs: usize) -> Slots {
+        Slots {
+            free: Mutex::new(jobs),
+            available: Condvar::new(),
+        }
+    }
+
+    fn call(request: &str) -> Value {
+        let writer: Writer = Arc::new(Mutex::new(Box::new(io::sink())));
+
+        dispatch(request, &writer, &slots(1))
+    }
+
+    fn error_code(response: &Value) -> Option<i64> {
+        response["error"]["code"].as_i64()
+    }
+
+    #[test]
+    fn dispatch_errors() {
+        assert_eq!(error_code(&call("{")), Some(PARSE_ERROR));
+        assert_eq!(error_code(&call(r#"{"id": 1}"#)), Some(INVALID_REQUEST));
+        assert_eq!(error_code(&call(r#"{"id": 1, "method": "fly"}"#)), Some(METHOD_NOT_FOUND));
+        assert_eq!(
+            error_code(&call(r#"{"id": 1, "method": "solve", "params": {"size": 2}}"#)),
+            Some(INVALID_PARAMS)
+        );
+        let tiles: Vec<u64> = (0..36).collect();
+        let large = json!({"id": 1, "method": "solve", "params": {"size": 6, "tiles": tiles}});
+        assert_eq!(error_code(&call(&large.to_string())), Some(INVALID_PARAMS));
+        assert_eq!(call(r#"{"id": 7, "method": "fly"}"#)["id"], json!(7));
+    }
+
+    #[test]
+    fn dispatch_methods() {
+        let solvable = call(r#"{"id": 1, "method": "check-solvable", "params": {"size": 3, "tiles": [1, 2, 3, 8, 0, 4, 7, 6, 5]}}"#);
+        assert_eq!(solvable["result"]["solvable"], json!(true));
+
+        let generated = call(r#"{"id": 2, "method": "generate", "params": {"size": 3, "seed": 4}}"#);
+        assert_eq!(generated["result"]["tiles"].as_array().map(|t| t.len()), Some(9));
+        assert_eq!(generated, call(r#"{"id": 2, "method": "generate", "params": {"size": 3, "seed": 4}}"#));
+
+        let verified = call(r#"{"id": 3, "method": "verify-solution", "params": {"size": 3, "goal": "classic", "tiles": [1, 2, 3, 4, 5, 6, 7, 0, 8], "moves": "L"}}"#);
+        assert_eq!(verified["result"], json!({ "valid": true, "solved": true, "length": 1 }));
+    }
+
+    #[test]
+    fn dispatch_solve() {
+        let solved = call(r#"{"id": 1, "method": "solve", "params": {"size": 3, "goal": "classic", "tiles": [1, 2, 3, 4, 5, 6, 0, 7, 8]}}"#);
+        assert_eq!(solved["result"]["status"], json!("solved"));
+        assert_eq!(solved["result"]["length"], json!(2));
+
+        let stopped = call(r#"{"id": 2, "method": "solve", "params": {"size": 3, "goal": "classic", "tiles": [7, 1, 8, 5, 2, 6, 3, 4, 0], "budget": {"expanded": 10}}}"#);
+        assert_eq!(stopped["result"]["status"], json!("out_of_budget"));
+        assert_eq!(stopped["result"]["budget"], json!("expanded"));
+
+        let unsolvable = call(r#"{"id": 3, "method": "solve", "params": {"size": 3, "goal": "classic", "tiles": [2, 1, 3, 4, 5, 6, 7, 8, 0]}}"#);
+        assert_eq!(unsolvable["result"]["status"], json!("unsolvable"));
+    }
+
+    #[test]
+    fn dispatch_solve_weights() {
+        let request = |weight: u64| {
+            call(&format!(
+                r#"{{"id": 1, "method": "solve", "params": {{"size": 3, "tiles": [1, 2, 3, 8, 0, 4, 7, 6, 5], "h_weight": {}}}}}"#,
+                weight
+            ))
+        };
+
+        assert_eq!(request(MAX_WEIGHT)["result"]["status"], json!("solved"));
+        assert_eq!(error_code(&request(MAX_WEIGHT + 1)), Some(INVALID_PARAMS));
+        assert_eq!(error_code(&request(u64::MAX)), Some(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn slot_given_back_on_panic() {
+        let slots = Arc::new(slots(1));
+        let taken = slots.clone();
+
+        let _ = thread::spawn(move || {
+            let _slot = taken.acquire();
+            panic!("search failed");
+        })
+        .join();
+        drop(slots.acquire());
+        assert_eq!(*slots.free.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn only_loopback_addresses() {
+        assert!(loopback_addresses("127.0.0.1:0").is_ok());
+        assert!(loopback_addresses("[::1]:0").is_ok());
+        assert!(loopback_addresses("0.0.0.0:0").is_err());
+        assert!(loopback_addresses("192.168.1.1:4000").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::process;
+
+use npuzzle_lib::*;
+use types::ParsedPuzzle;
+use types::Problem;
+use types::SearchOptions;
+use types::Status;
+
+use crate::parse_goal;
+use crate::solve;
+use crate::ParsedArgs;
+
+// Reads one puzzle per line on stdin and writes one line per puzzle on stdout:
+//   solved <moves> <length> <closed states> <max states> <microseconds>
+//   unsolvable
+//   error <line number> <message>
+// Moves are `-` for a puzzle already at its goal. Blank lines and comments
+// are skipped without an answer.
+pub fn run(template: &Problem, parsed: &ParsedArgs) {
+    let fixed_goal = match &parsed.goal as &str {
+        "snail" | "classic" => None,
+        "stdin" => {
+            println!("error 0 goal cannot be read from stdin in stream mode");
+            process::exit(1);
+        }
+        goal => match parse_goal(goal, 0) {
+            Some(g) => Some(g),
+            None => {
+                println!("error 0 could not parse goal {}", goal);
+                process::exit(1);
+            }
+        },
+    };
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    for (index, line) in stdin.lock().lines().enumerate() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                let _ = writeln!(out, "error {} {}", index + 1, e);
+                break;
+            }
+        };
+
+        let answer = match answer(&line, &fixed_goal, template, parsed) {
+            Some(Ok(a)) => a,
+            Some(Err(e)) => format!("error {} {}", index + 1, e),
+            None => continue,
+        };
+
+        // the reader on the other end may have gone away
+        if writeln!(out, "{}", answer).and_then(|_| out.flush()).is_err() {
+            return;
+        }
+    }
+}
+
+fn answer(
+    line: &str,
+    fixed_goal: &Option<ParsedPuzzle>,
+    template: &Problem,
+    parsed: &ParsedArgs,
+) -> Option<Result<String, String>> {
+    let input = match input_parser::parse_compact(line) {
+        Ok(Some(p)) => p,
+        Ok(None) => return None,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let goal = match fixed_goal {
+        Some(g) => g.container.clone(),
+        None if parsed.goal == "classic" => generator::classic(input.size),
+        None => generator::snail(input.size),
+    };
+
+    if goal.len() != input.container.len() {
+        return Some(Err(format!(
+            "mismatched puzzle sizes, expected {} tiles, got {}",
+            goal.len(),
+            input.container.len()
+        )));
+    }
+
+    if !checker::is_solvable(&input.container, &goal, input.size) {
+        return Some(Ok("unsolvable".to_string()));
+    }
+
+    let problem = Problem {
+        start: input.container,
+        end: goal,
+        size: input.size,
+        heuristic: template.heuristic,
+        g_weight: template.g_weight,
+        h_weight: template.h_weight,
+    };
+
+    let solution = match solve(&problem, parsed, &mut SearchOptions::default()) {
+        Ok(s) => s,
+        Err(e) => return Some(Err(format!("disk search failed: {}", e))),
+    };
+
+    Some(match solution.status {
+        Status::Solved => {
+            let moves = game::to_moves(&solution.path, problem.size);
+
+            Ok(format!(
+                "solved {} {} {} {} {}",
+                if moves.is_empty() { "-" } else { &moves },
+                moves.len(),
+                solution.closed_states,
+                solution.max_states,
+                solution.time.as_micros()
+            ))
+        }
+        Status::Unsolvable => Ok("unsolvable".to_string()),
+        Status::Cancelled | Status::InProgress => Err("search cancelled".to_string()),
+        Status::OutOfBudget => Err("search ran out of budget".to_string()),
+    })
+}